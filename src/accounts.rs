@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{GuildId, Http, UserId},
+    futures::TryStreamExt,
+};
+
+use crate::structs::{VerificationState, VerifiedUser};
+
+/// Returns the verified links of the member, the primary one first.
+pub async fn verified(guild_id: GuildId, user_id: UserId) -> mongodb::error::Result<Vec<VerifiedUser>> {
+    let query = doc! {
+        "id": user_id.to_string(),
+        "guild": guild_id.to_string(),
+        "state": VerificationState::Verified.as_str(),
+    };
+    crate::links::coll()
+        .find(query)
+        .sort(doc! { "primary": -1, "habbo": 1 })
+        .await?
+        .try_collect()
+        .await
+}
+
+/// Whether the Habbo is one of `links`. Links without a unique id are compared by name.
+pub fn is_linked(links: &[VerifiedUser], hotel: &str, habbo: &str, unique_id: &str) -> bool {
+    links.iter().any(|link| {
+        *link.hotel == *hotel
+            && match link.unique_id.is_empty() || unique_id.is_empty() {
+                true => link.habbo.to_lowercase() == habbo.to_lowercase(),
+                false => *link.unique_id == *unique_id,
+            }
+    })
+}
+
+/// Whether the member has a primary Habbo.
+pub async fn has_primary(guild_id: GuildId, user_id: UserId) -> bool {
+    let query = doc! {
+        "id": user_id.to_string(),
+        "guild": guild_id.to_string(),
+        "state": VerificationState::Verified.as_str(),
+        "primary": true,
+    };
+    !matches!(crate::links::coll().find_one(query).await, Ok(None))
+}
+
+/// Gives the member the roles granted for the Habbo of the link and the nickname.
+async fn sync(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, link: &VerifiedUser) {
+    match crate::habbo_api::profile_of(link).await {
+        Ok(profile) => {
+            crate::roles::sync_badges(http, guild_id, user_id, &profile).await;
+            if let Some(member_since) = link.member_since.or_else(|| profile.member_since()) {
+                crate::roles::sync_age(http, guild_id, user_id, member_since).await;
+            }
+            crate::roles::sync_presence(http, guild_id, user_id, profile.online).await;
+        }
+        Err(e) => warn!("Cannot retrieve the Habbo `{}` of the Discord user {}: {}", link.habbo, user_id, e),
+    }
+    crate::nicknames::sync(http, guild_id, user_id).await;
+}
+
+/// Makes the link the primary link of the member and syncs the roles and nickname with it.
+pub async fn set_primary(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, link: &VerifiedUser) -> bool {
+    let coll = crate::links::coll();
+
+    // The unique index allows only one primary link, so the old one is unset first.
+    let query = doc! {
+        "id": user_id.to_string(),
+        "guild": guild_id.to_string(),
+        "primary": true,
+    };
+    if let Err(e) = coll.update_many(query, doc! { "$set": { "primary": false } }).await {
+        error!("{}", e);
+        return false;
+    }
+
+    let mut query = crate::links::filter(link);
+    query.insert("state", VerificationState::Verified.as_str());
+    match coll.update_one(query, doc! { "$set": { "primary": true } }).await {
+        Ok(r) if r.matched_count > 0 => {}
+        Ok(_) => return false,
+        Err(e) => {
+            error!("{}", e);
+            return false;
+        }
+    }
+
+    sync(http, guild_id, user_id, link).await;
+    true
+}
+
+/// Called after a verified link of the member has been removed. If it was the primary link,
+/// another one becomes primary. All roles and the nickname are removed if no link is left.
+pub async fn removed(http: &Arc<Http>, guild_id: GuildId, user_id: UserId) {
+    let links = match verified(guild_id, user_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    match links.first() {
+        None => {
+            crate::roles::strip_all(http, guild_id, user_id).await;
+            crate::nicknames::clear(http, guild_id, user_id).await;
+        }
+        Some(link) if !link.primary => {
+            set_primary(http, guild_id, user_id, link).await;
+        }
+        Some(_) => {}
+    }
+}
@@ -0,0 +1,37 @@
+use clap::Parser;
+
+const PRECEDENCE: &str = "\
+Precedence (highest first):
+  1. Command line flags, e.g. --config
+  2. Environment variables:
+       ORIGINSBOT_CONFIG            path of the settings file
+       ORIGINSBOT_LOGGING           path of the logging configuration
+       ORIGINSBOT_TOKEN             overrides `token`
+       ORIGINSBOT_MONGODB_URI       overrides `mongodb.uri`
+       ORIGINSBOT_MONGODB_DATABASE  overrides `mongodb.database`
+  3. The settings file
+  4. Built-in defaults
+
+Values from environment variables are never written to the settings file.";
+
+/// Discord bot to verify Habbo Hotel:Origins accounts.
+#[derive(Parser)]
+#[command(version, about, after_help = PRECEDENCE)]
+pub struct Args {
+    /// Path of the settings file.
+    #[arg(long, env = "ORIGINSBOT_CONFIG", default_value = "settings.json")]
+    pub config: String,
+    /// Path of the log4rs configuration.
+    #[arg(long, env = "ORIGINSBOT_LOGGING", default_value = "logging.yaml")]
+    pub logging: String,
+    /// Validate the settings, print all problems and exit. Exits with 1 if there are problems.
+    #[arg(long)]
+    pub check_config: bool,
+}
+
+/// Parses the command line once. Exits the process on `--help`, `--version` and invalid arguments.
+pub fn args() -> &'static Args {
+    use std::sync::OnceLock;
+    static VAL: OnceLock<Args> = OnceLock::new();
+    VAL.get_or_init(Args::parse)
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{CommandInteraction, Http, ResolvedOption, ResolvedValue},
+    builder::CreateCommand,
+};
+
+use crate::structs::VerifiedUser;
+
+/// Finds the links of the Habbo. The hotel is only needed if the member linked Habbos
+/// with the same name on several hotels, otherwise more than one link is returned.
+pub fn find<'a>(links: &'a [VerifiedUser], habbo: &str, hotel: Option<&str>) -> Vec<&'a VerifiedUser> {
+    let habbo = crate::helper::normalize_habbo(habbo).to_lowercase();
+    links
+        .iter()
+        .filter(|link| link.habbo.to_lowercase() == habbo)
+        .filter(|link| hotel.is_none_or(|hotel| *link.hotel == *hotel))
+        .collect()
+}
+
+fn list(interaction: &CommandInteraction, links: &[VerifiedUser], limit: u32) -> String {
+    if links.is_empty() {
+        return format!(
+            "Hello <@{}> :)\n\nYou have not linked any Habbo yet! Use the command `/verify` to link one.",
+            interaction.user.id,
+        );
+    }
+
+    let settings = crate::settings();
+    let lines = links
+        .iter()
+        .map(|link| {
+            let hotel = settings
+                .get_hotel(&link.hotel)
+                .map_or(&*link.hotel, |hotel| hotel.get_name());
+            match link.primary {
+                true => format!("`{}` on {} (primary)", link.habbo, hotel),
+                false => format!("`{}` on {}", link.habbo, hotel),
+            }
+        })
+        .collect::<Vec<_>>();
+    format!(
+        "Hello <@{}> :)\n\n**Your Habbos ({}/{}):**\n{}",
+        interaction.user.id,
+        links.len(),
+        limit,
+        lines.join("\n"),
+    )
+}
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    let guild_id = crate::require_guild!(interaction);
+    let config = crate::guilds::get(guild_id).await;
+    crate::check_role_available!(config, interaction.user.id.get());
+
+    let options = interaction.data.options();
+    let (name, sub) = match options.first() {
+        Some(ResolvedOption { name, value: ResolvedValue::SubCommand(sub), .. }) => (*name, sub),
+        _ => return "Oops!".into(),
+    };
+    let (mut habbo, mut hotel) = (None, None);
+    for option in sub {
+        match (option.name, &option.value) {
+            ("habbo", ResolvedValue::String(val)) => habbo = Some(*val),
+            ("hotel", ResolvedValue::String(val)) => hotel = Some(*val),
+            _ => {}
+        }
+    }
+
+    let user_id = interaction.user.id;
+    let links = match crate::accounts::verified(guild_id, user_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", user_id);
+        }
+    };
+    if name == "list" {
+        return list(interaction, &links, config.max_accounts.max(1));
+    }
+
+    let found = match habbo {
+        Some(habbo) => find(&links, habbo, hotel),
+        None => return format!("Hello <@{}> :)\n\nThe Habbo is missing!", user_id),
+    };
+    let link = match found[..] {
+        [link] => link,
+        [] => return format!(
+            "Hello <@{}> :)\n\nYou have not linked this Habbo! Use the command `/accounts list` to see all your Habbos.",
+            user_id,
+        ),
+        _ => return format!(
+            "Hello <@{}> :)\n\nYou have linked Habbos with this name on several hotels! Please select the hotel.",
+            user_id,
+        ),
+    };
+
+    match name {
+        "primary" if link.primary => format!("Hello <@{}> :)\n\nThe Habbo `{}` is already your primary Habbo!", user_id, link.habbo),
+        "primary" => match crate::accounts::set_primary(http, guild_id, user_id, link).await {
+            true => format!(
+                "Hello <@{}> :)\n\nThe Habbo `{}` is now your primary Habbo! Your roles have been updated.",
+                user_id,
+                link.habbo,
+            ),
+            false => format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", user_id),
+        },
+        "unlink" => {
+            if let Err(e) = crate::links::reset(http, crate::links::filter(link), "unlinked by the user").await {
+                error!("{}", e);
+                return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", user_id);
+            }
+            crate::accounts::removed(http, guild_id, user_id).await;
+            format!("Hello <@{}> :)\n\nThe Habbo `{}` has been unlinked!", user_id, link.habbo)
+        }
+        _ => "Oops!".into(),
+    }
+}
+
+fn habbo_options(option: serenity::all::CreateCommandOption) -> serenity::all::CreateCommandOption {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    option
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::String, "habbo", "The name of the Habbo")
+                .required(true),
+        )
+        .add_sub_option(crate::helper::hotel_option())
+}
+
+pub fn register() -> CreateCommand {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    CreateCommand::new("accounts")
+        .description("Manage your linked Habbos")
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show all your linked Habbos"))
+        .add_option(habbo_options(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "primary",
+            "Select the Habbo your roles and nickname are based on",
+        )))
+        .add_option(habbo_options(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "unlink",
+            "Unlink one of your Habbos",
+        )))
+}
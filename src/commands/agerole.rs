@@ -0,0 +1,140 @@
+use std::{collections::HashSet, sync::Arc};
+
+use serenity::{
+    all::{CommandInteraction, GuildId, Http, ResolvedOption, ResolvedValue},
+    builder::CreateCommand,
+};
+
+use crate::structs::AgeRole;
+
+async fn add(interaction: &CommandInteraction, guild_id: GuildId, options: &[ResolvedOption<'_>]) -> String {
+    let (mut role, mut min_days, mut joined_before) = (None, 0, None);
+    for option in options {
+        match (option.name, &option.value) {
+            ("role", ResolvedValue::Role(val)) => role = Some(val.id),
+            ("min_days", ResolvedValue::Integer(val)) => min_days = (*val).max(0) as u64,
+            ("joined_before", ResolvedValue::String(val)) => joined_before = Some(val.trim()),
+            _ => {}
+        }
+    }
+    let role = match role {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nThe role is missing!", interaction.user.id),
+    };
+    if joined_before.is_some_and(|date| bson::DateTime::parse_rfc3339_str(date).is_err()) {
+        return format!(
+            "Hello <@{}> :)\n\nThe date is invalid! Expected an RFC 3339 date, e.g. `2024-07-01T00:00:00Z`.",
+            interaction.user.id,
+        );
+    }
+
+    // Each role has one tier, so adding it again replaces its conditions.
+    let mut config = crate::guilds::get(guild_id).await;
+    config.age_roles.retain(|age_role| age_role.get_role_id() != role.get());
+    config.age_roles.push(AgeRole::new(role.get(), min_days, joined_before));
+    if !crate::guilds::save(config).await {
+        return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+    }
+
+    format!(
+        "Hello <@{}> :)\n\nVerified members whose Habbo {} will receive the role <@&{}>!",
+        interaction.user.id,
+        describe(min_days, joined_before),
+        role,
+    )
+}
+
+async fn remove(http: &Arc<Http>, interaction: &CommandInteraction, guild_id: GuildId, options: &[ResolvedOption<'_>]) -> String {
+    let role = match options.iter().find(|option| option.name == "role").map(|option| &option.value) {
+        Some(ResolvedValue::Role(val)) => val.id,
+        _ => return format!("Hello <@{}> :)\n\nThe role is missing!", interaction.user.id),
+    };
+
+    let mut config = crate::guilds::get(guild_id).await;
+    let before = config.age_roles.len();
+    config.age_roles.retain(|age_role| age_role.get_role_id() != role.get());
+    if config.age_roles.len() == before {
+        return format!("Hello <@{}> :)\n\nThe role <@&{}> is not an age role!", interaction.user.id, role);
+    }
+    if !crate::guilds::save(config).await {
+        return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+    }
+
+    crate::roles::strip_from_members(http, guild_id, HashSet::from([role]));
+    format!(
+        "Hello <@{}> :)\n\nThe age role <@&{}> has been removed! The role is being removed from all verified members.",
+        interaction.user.id,
+        role,
+    )
+}
+
+async fn list(interaction: &CommandInteraction, guild_id: GuildId) -> String {
+    let config = crate::guilds::get(guild_id).await;
+    if config.age_roles.is_empty() {
+        return format!("Hello <@{}> :)\n\nNo age roles have been set!", interaction.user.id);
+    }
+
+    let lines = config
+        .age_roles
+        .iter()
+        .map(|age_role| format!("<@&{}>: {}", age_role.get_role_id(), describe(age_role.get_min_days(), age_role.get_joined_before())))
+        .collect::<Vec<_>>();
+    format!(
+        "Hello <@{}> :)\n\n**Age roles:**\n{}",
+        interaction.user.id,
+        lines.join("\n"),
+    )
+}
+
+/// Describes the conditions of an age role, e.g. "is at least 365 days old".
+fn describe(min_days: u64, joined_before: Option<&str>) -> String {
+    match joined_before {
+        Some(date) if min_days > 0 => format!("is at least {} days old and was created before `{}`", min_days, date),
+        Some(date) => format!("was created before `{}`", date),
+        None => format!("is at least {} days old", min_days),
+    }
+}
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    crate::check_admin!(interaction);
+    let guild_id = crate::require_guild!(interaction);
+
+    let options = interaction.data.options();
+    match options.first() {
+        Some(ResolvedOption { name: "add", value: ResolvedValue::SubCommand(sub), .. }) => add(interaction, guild_id, sub).await,
+        Some(ResolvedOption { name: "remove", value: ResolvedValue::SubCommand(sub), .. }) => remove(http, interaction, guild_id, sub).await,
+        Some(ResolvedOption { name: "list", .. }) => list(interaction, guild_id).await,
+        _ => "Oops!".into(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    CreateCommand::new("agerole")
+        .description("Manage the roles verified members receive for the age of their Habbo")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Give a role for the age of a Habbo")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "The role for the age")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "min_days", "Minimum age of the Habbo in days, e.g. 365")
+                        .min_int_value(0)
+                        .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "joined_before", "The Habbo must have been created before, e.g. 2024-07-01T00:00:00Z")
+                        .required(false),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Stop giving an age role")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "The age role")
+                        .required(true),
+                ),
+        )
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show all age roles"))
+}
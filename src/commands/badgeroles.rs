@@ -0,0 +1,166 @@
+use std::{collections::HashSet, sync::Arc};
+
+use serenity::{
+    all::{CommandInteraction, GuildId, Http, ResolvedOption, ResolvedValue, RoleId},
+    builder::CreateCommand,
+    futures::TryStreamExt,
+};
+
+use crate::roles::{BadgeRole, normalize_badge};
+
+async fn add(interaction: &CommandInteraction, guild_id: GuildId, options: &[ResolvedOption<'_>]) -> String {
+    let (mut badge, mut role) = (None, None);
+    for option in options {
+        match (option.name, &option.value) {
+            ("badge", ResolvedValue::String(val)) => badge = Some(normalize_badge(val)),
+            ("role", ResolvedValue::Role(val)) => role = Some(val.id),
+            _ => {}
+        }
+    }
+    let (badge, role) = match (badge, role) {
+        (Some(badge), Some(role)) if !badge.is_empty() => (badge, role),
+        _ => return format!("Hello <@{}> :)\n\nThe badge or role is missing!", interaction.user.id),
+    };
+
+    let guild = guild_id.to_string();
+    let query = doc! {
+        "guild": &guild,
+        "badge": &badge,
+        "role": role.to_string(),
+    };
+    let badge_role = BadgeRole {
+        guild,
+        badge: badge.as_str().into(),
+        role: role.to_string(),
+    };
+    let options = mongodb::options::ReplaceOptions::builder().upsert(true).build();
+    if let Err(e) = crate::roles::badge_coll().replace_one(query, badge_role).with_options(options).await {
+        error!("{}", e);
+        return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+    }
+
+    format!(
+        "Hello <@{}> :)\n\nVerified members wearing the badge `{}` will receive the role <@&{}>!",
+        interaction.user.id,
+        badge,
+        role,
+    )
+}
+
+async fn remove(http: &Arc<Http>, interaction: &CommandInteraction, guild_id: GuildId, options: &[ResolvedOption<'_>]) -> String {
+    let mut query = doc! {
+        "guild": guild_id.to_string(),
+    };
+    for option in options {
+        match (option.name, &option.value) {
+            ("badge", ResolvedValue::String(val)) => {
+                query.insert("badge", normalize_badge(val));
+            }
+            ("role", ResolvedValue::Role(val)) => {
+                query.insert("role", val.id.to_string());
+            }
+            _ => {}
+        }
+    }
+    if !query.contains_key("badge") {
+        return format!("Hello <@{}> :)\n\nThe badge is missing!", interaction.user.id);
+    }
+
+    let coll = crate::roles::badge_coll();
+    let removed: Vec<BadgeRole> = match coll.find(query.clone()).await {
+        Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+        Err(e) => {
+            error!("{}", e);
+            return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+        }
+    };
+    if let Err(e) = coll.delete_many(query).await {
+        error!("{}", e);
+        return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+    }
+
+    // A role can also be given for another badge.
+    let kept = crate::roles::badge_roles(guild_id)
+        .await
+        .into_iter()
+        .map(|badge_role| badge_role.role)
+        .collect::<HashSet<_>>();
+    let roles = removed
+        .iter()
+        .filter(|badge_role| !kept.contains(&badge_role.role))
+        .filter_map(|badge_role| badge_role.role.parse::<u64>().ok())
+        .map(RoleId::new)
+        .collect::<HashSet<_>>();
+    let note = match roles.is_empty() {
+        true => "",
+        false => " The roles are being removed from all verified members.",
+    };
+    crate::roles::strip_from_members(http, guild_id, roles);
+
+    format!(
+        "Hello <@{}> :)\n\n{} badge role(s) removed!{}",
+        interaction.user.id,
+        removed.len(),
+        note,
+    )
+}
+
+async fn list(interaction: &CommandInteraction, guild_id: GuildId) -> String {
+    let badge_roles = crate::roles::badge_roles(guild_id).await;
+    if badge_roles.is_empty() {
+        return format!("Hello <@{}> :)\n\nNo badge roles have been set!", interaction.user.id);
+    }
+
+    let lines = badge_roles
+        .iter()
+        .map(|badge_role| format!("`{}` → <@&{}>", badge_role.badge, badge_role.role))
+        .collect::<Vec<_>>();
+    format!(
+        "Hello <@{}> :)\n\n**Badge roles:**\n{}",
+        interaction.user.id,
+        lines.join("\n"),
+    )
+}
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    crate::check_admin!(interaction);
+    let guild_id = crate::require_guild!(interaction);
+
+    let options = interaction.data.options();
+    match options.first() {
+        Some(ResolvedOption { name: "add", value: ResolvedValue::SubCommand(sub), .. }) => add(interaction, guild_id, sub).await,
+        Some(ResolvedOption { name: "remove", value: ResolvedValue::SubCommand(sub), .. }) => remove(http, interaction, guild_id, sub).await,
+        Some(ResolvedOption { name: "list", .. }) => list(interaction, guild_id).await,
+        _ => "Oops!".into(),
+    }
+}
+
+pub fn register() -> CreateCommand {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    CreateCommand::new("badgeroles")
+        .description("Manage the roles verified members receive for their Habbo badges")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "add", "Give a role for a badge")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "badge", "The badge code, e.g. ADM")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "The role for the badge")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "remove", "Stop giving roles for a badge")
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "badge", "The badge code, e.g. ADM")
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "Only remove this role")
+                        .required(false),
+                ),
+        )
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "list", "Show all badge roles"))
+}
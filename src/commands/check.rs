@@ -1,51 +1,125 @@
 use serenity::{
-    all::CommandInteraction,
+    all::{CommandDataOptionValue, CommandInteraction},
     builder::CreateCommand,
 };
 
-use crate::structs::VerifiedUser;
+use crate::structs::VerificationState;
 
 pub async fn run(interaction: &CommandInteraction) -> String {
-    if interaction.data.options.is_empty() {
-        return format!(
-            "Hello <@{}> :)\n\nThe user is missing!",
-            interaction.user.id,
-        );
+    let guild_id = crate::require_guild!(interaction);
+
+    let (mut user_id, mut habbo) = (None, None);
+    for option in &interaction.data.options {
+        match (option.name.as_str(), &option.value) {
+            ("user", CommandDataOptionValue::User(val)) => user_id = Some(*val),
+            ("habbo", CommandDataOptionValue::String(val)) => habbo = Some(crate::helper::normalize_habbo(val)),
+            _ => {}
+        }
     }
 
-    if let Some(cmd_data) = interaction.data.options.first() {
-        let user_id = match cmd_data.value.as_user_id() {
-            Some(r) => r,
-            None => {
-                return format!(
-                    "Hello <@{}> :)\n\nPlease use a valid user!",
-                    interaction.user.id,
-                );
-            }
-        };
+    let hotel = match crate::helper::hotel(interaction) {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nThis hotel is not available!", interaction.user.id),
+    };
 
-        let coll = crate::mongo::get_coll::<VerifiedUser>("verified_users");
-        let query = doc! {
+    let query = match (user_id, &habbo) {
+        (Some(user_id), _) => doc! {
             "id": user_id.get().to_string(),
-            "verified": true,
-        };
+            "guild": guild_id.to_string(),
+        },
+        (None, Some(habbo)) => doc! {
+            "guild": guild_id.to_string(),
+            "hotel": hotel.get_id(),
+            "habbo": habbo,
+            "state": VerificationState::Verified.as_str(),
+        },
+        (None, None) => {
+            return format!(
+                "Hello <@{}> :)\n\nThe user or Habbo is missing!",
+                interaction.user.id,
+            );
+        }
+    };
+
+    let coll = crate::links::coll();
+    let mut others = Vec::new();
+    let found = match user_id {
+        // Members can link several Habbos, the primary one is shown first.
+        Some(user_id) => match crate::accounts::verified(guild_id, user_id).await {
+            Ok(mut links) if !links.is_empty() => {
+                others = links.split_off(1);
+                Ok(links.pop())
+            }
+            Ok(_) => coll.find_one(query).await,
+            Err(e) => Err(e),
+        },
+        None => coll.find_one(query).collation(crate::mongo::habbo_collation()).await,
+    };
+    if let Ok(user_o) = found {
+        if let Some(user) = user_o {
+            // Why the link is in its current state.
+            let mut details = match user.last_transition() {
+                Some(t) => format!(
+                    "\n\n**state:** `{}` since {}\n**reason:** {}",
+                    user.state,
+                    crate::helper::timestamp(t.at),
+                    t.reason,
+                ),
+                None => format!("\n\n**state:** `{}`", user.state),
+            };
+            if let (Some(flagged_at), Some(reason)) = (user.flagged_at, &user.flag_reason) {
+                details.push_str(&format!(
+                    "\n**flagged:** since {}, {}",
+                    crate::helper::timestamp(flagged_at),
+                    reason,
+                ));
+            }
 
-        if let Ok(user_o) = coll.find_one(query).await {
-            if let Some(user) = user_o {
+            if !others.is_empty() {
+                let habbos = others
+                    .iter()
+                    .map(|link| format!("`{}`", link.habbo))
+                    .collect::<Vec<_>>();
+                details.push_str(&format!("\n**other Habbos:** {}", habbos.join(", ")));
+            }
+
+            if user.state == VerificationState::Verified {
+                let settings = crate::settings();
+                let hotel_name = settings
+                    .get_hotel(&user.hotel)
+                    .map_or(&*user.hotel, |hotel| hotel.get_name());
                 return format!(
-                    "Hello <@{}> :)\n\nThe user <@{}> is verified as Habbo `{}`!",
+                    "Hello <@{}> :)\n\nThe user <@{}> is verified as Habbo `{}` on {}!{}",
                     interaction.user.id,
                     user.id,
                     user.habbo,
+                    hotel_name,
+                    details,
                 );
             }
 
+            return format!(
+                "Hello <@{}> :)\n\nThe user <@{}> is not verified!{}",
+                interaction.user.id,
+                user.id,
+                details,
+            );
+        }
+
+        if let Some(user_id) = user_id {
             return format!(
                 "Hello <@{}> :)\n\nThe user <@{}> is not verified!",
                 interaction.user.id,
                 user_id.get(),
             );
         }
+
+        return format!(
+            "Hello <@{}> :)\n\nThe Habbo `{}` on {} is not linked to any user!",
+            interaction.user.id,
+            habbo.unwrap_or_default(),
+            hotel.get_name(),
+        );
     }
 
     format!(
@@ -58,13 +132,22 @@ pub fn register() -> CreateCommand {
     use serenity::all::{CreateCommandOption, CommandOptionType};
 
     CreateCommand::new("check")
-        .description("Check the verification of a user")
+        .description("Check the verification of a user or Habbo")
         .add_option(
             CreateCommandOption::new(
                 CommandOptionType::User,
                 "user",
                 "Select the user to check",
             )
-            .required(true),
+            .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::String,
+                "habbo",
+                "The name of the Habbo to check",
+            )
+            .required(false),
         )
+        .add_option(crate::helper::hotel_option())
 }
@@ -0,0 +1,348 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{Channel, ChannelId, CommandInteraction, GuildId, Http, ResolvedOption, ResolvedValue, RoleId, UserId},
+    builder::CreateCommand,
+};
+
+use crate::structs::{ConflictPolicy, GuildConfig, Settings};
+
+/// The settings which can be changed with `/config`.
+/// `verification_window` and `code_format` are stored in the settings file and apply to all servers,
+/// so only the owner of the bot can change them, see `is_owner`.
+const KEYS: [&str; 13] = [
+    "verification_window",
+    "code_format",
+    "log_channel",
+    "mod_channel",
+    "audit_channel",
+    "nickname",
+    "max_accounts",
+    "conflict_policy",
+    "ephemeral_replies",
+    "badge_roles",
+    "age_roles",
+    "presence_role",
+    "presence_role_id",
+];
+
+/// Upper limit for `max_accounts`.
+const MAX_ACCOUNTS: u32 = 10;
+
+fn on_off(val: bool) -> &'static str {
+    match val {
+        true => "on",
+        false => "off",
+    }
+}
+
+fn parse_bool(val: &str) -> Option<bool> {
+    match val.trim().to_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => Some(true),
+        "off" | "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// The switch of a guild configuration behind `key`, if `key` is a switch.
+fn switch<'a>(config: &'a mut GuildConfig, key: &str) -> Option<&'a mut bool> {
+    match key {
+        "ephemeral_replies" => Some(&mut config.ephemeral_replies),
+        "badge_roles" => Some(&mut config.badge_roles_enabled),
+        "age_roles" => Some(&mut config.age_roles_enabled),
+        "presence_role" => Some(&mut config.presence_role_enabled),
+        _ => None,
+    }
+}
+
+fn describe(settings: &Settings, config: &GuildConfig, key: &str) -> String {
+    let verification = settings.get_verification();
+    match key {
+        "verification_window" => format!("{} seconds (all servers)", verification.get_window()),
+        "code_format" => format!("`{}` (all servers)", verification.get_code_format()),
+        "log_channel" if config.log_channel_id >= crate::LOWEST_ID => format!("<#{}>", config.log_channel_id),
+        "log_channel" => "not set".to_string(),
+        "mod_channel" if config.mod_channel_id >= crate::LOWEST_ID => format!("<#{}>", config.mod_channel_id),
+        "mod_channel" => "not set, the log channel is used".to_string(),
+        "audit_channel" if config.audit_channel_id >= crate::LOWEST_ID => format!("<#{}>", config.audit_channel_id),
+        "audit_channel" => "not set".to_string(),
+        "nickname" if !config.nickname_template.is_empty() => format!("`{}`", config.nickname_template),
+        "nickname" => "off".to_string(),
+        "max_accounts" => format!("{} Habbo(s) per member", config.max_accounts),
+        "conflict_policy" => format!("`{}`", config.conflict_policy.as_str()),
+        "ephemeral_replies" => on_off(config.ephemeral_replies).to_string(),
+        "badge_roles" => on_off(config.badge_roles_enabled).to_string(),
+        "age_roles" => on_off(config.age_roles_enabled).to_string(),
+        "presence_role" => on_off(config.presence_role_enabled).to_string(),
+        "presence_role_id" if config.presence_role_id >= crate::LOWEST_ID => format!("<@&{}>", config.presence_role_id),
+        "presence_role_id" => "not set".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+async fn view(interaction: &CommandInteraction, guild_id: GuildId) -> String {
+    let settings = crate::settings();
+    let config = crate::guilds::get(guild_id).await;
+
+    let lines = KEYS
+        .iter()
+        .map(|key| format!("`{}`: {}", key, describe(&settings, &config, key)))
+        .collect::<Vec<_>>();
+    format!(
+        "Hello <@{}> :)\n\n**Configuration:**\n{}",
+        interaction.user.id,
+        lines.join("\n"),
+    )
+}
+
+/// Whether the user owns the application of the bot or is a member of its team.
+async fn is_owner(http: &Arc<Http>, user_id: UserId) -> bool {
+    let info = match http.get_current_application_info().await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load the application info: {}", e);
+            return false;
+        }
+    };
+
+    info.owner.is_some_and(|owner| owner.id == user_id)
+        || info.team.is_some_and(|team| team.members.iter().any(|member| member.user.id == user_id))
+}
+
+/// Parses a channel mention or id and makes sure the channel belongs to the guild.
+async fn channel(http: &Arc<Http>, guild_id: GuildId, val: &str) -> Option<u64> {
+    let id = val
+        .trim()
+        .trim_start_matches("<#")
+        .trim_end_matches('>')
+        .parse::<u64>()
+        .ok()
+        .filter(|id| *id >= crate::LOWEST_ID)?;
+
+    match http.get_channel(ChannelId::new(id)).await {
+        Ok(Channel::Guild(channel)) if channel.guild_id == guild_id => Some(id),
+        _ => None,
+    }
+}
+
+/// Parses a role mention or id and makes sure the role belongs to the guild.
+async fn role(http: &Arc<Http>, guild_id: GuildId, val: &str) -> Option<u64> {
+    let id = val
+        .trim()
+        .trim_start_matches("<@&")
+        .trim_end_matches('>')
+        .parse::<u64>()
+        .ok()
+        .filter(|id| *id >= crate::LOWEST_ID)?;
+
+    match http.get_guild_roles(guild_id).await {
+        Ok(roles) if roles.iter().any(|role| role.id.get() == id) => Some(id),
+        _ => None,
+    }
+}
+
+/// Changes the setting `key` to `value`, or back to its default if `value` is `None`.
+async fn change(
+    http: &Arc<Http>,
+    interaction: &CommandInteraction,
+    guild_id: GuildId,
+    key: &str,
+    value: Option<&str>,
+) -> String {
+    let invalid = |expected: &str| {
+        format!(
+            "Hello <@{}> :)\n\nThe value for `{}` is invalid! Expected {}.",
+            interaction.user.id,
+            key,
+            expected,
+        )
+    };
+
+    if matches!(key, "verification_window" | "code_format") && !is_owner(http, interaction.user.id).await {
+        return format!(
+            "Hello <@{}> :)\n\nThe setting `{}` applies to all servers, so only the owner of the bot can change it!",
+            interaction.user.id,
+            key,
+        );
+    }
+
+    let previous = crate::guilds::get(guild_id).await;
+    let result = match key {
+        "verification_window" => {
+            let window = match value.map(|val| val.trim().parse::<u64>()) {
+                Some(Ok(r)) => Some(r),
+                Some(Err(_)) => return invalid("a number of seconds"),
+                None => None,
+            };
+            crate::config::update(|settings| {
+                let verification = settings.get_verification_mut();
+                match window {
+                    Some(window) => verification.set_window(window),
+                    None => verification.reset_window(),
+                }
+            })
+            .map_err(|e| e.to_string())
+        }
+        "code_format" => crate::config::update(|settings| {
+            let verification = settings.get_verification_mut();
+            match value {
+                Some(format) => verification.set_code_format(format.trim()),
+                None => verification.reset_code_format(),
+            }
+        })
+        .map_err(|e| e.to_string()),
+        _ => {
+            let mut config = crate::guilds::get(guild_id).await;
+            if key == "presence_role_id" {
+                config.presence_role_id = match value {
+                    Some(val) => match role(http, guild_id, val).await {
+                        Some(r) => r,
+                        None => return invalid("a role of this server, e.g. @Online"),
+                    },
+                    None => 0,
+                };
+            } else if matches!(key, "log_channel" | "mod_channel" | "audit_channel") {
+                let id = match value {
+                    Some(val) => match channel(http, guild_id, val).await {
+                        Some(r) => r,
+                        None => return invalid("a channel of this server, e.g. #mod-log"),
+                    },
+                    None => 0,
+                };
+                match key {
+                    "log_channel" => config.log_channel_id = id,
+                    "mod_channel" => config.mod_channel_id = id,
+                    _ => config.audit_channel_id = id,
+                }
+            } else if key == "nickname" {
+                config.nickname_template = match value.map(str::trim) {
+                    Some(val) if crate::nicknames::is_valid(val) => val.to_string(),
+                    Some(_) => return invalid("a template with `{habbo}`, e.g. `{discord} | {habbo}`"),
+                    None => String::new(),
+                };
+            } else if key == "max_accounts" {
+                config.max_accounts = match value.map(|val| val.trim().parse::<u32>()) {
+                    Some(Ok(r)) if (1..=MAX_ACCOUNTS).contains(&r) => r,
+                    Some(_) => return invalid(&format!("a number between 1 and {}", MAX_ACCOUNTS)),
+                    None => GuildConfig::default().max_accounts,
+                };
+            } else if key == "conflict_policy" {
+                config.conflict_policy = match value.map(ConflictPolicy::parse) {
+                    Some(Some(r)) => r,
+                    Some(None) => return invalid("`transfer`, `approval` or `deny`"),
+                    None => GuildConfig::default().conflict_policy,
+                };
+            } else {
+                let default = match switch(&mut GuildConfig::default(), key) {
+                    Some(r) => *r,
+                    None => return format!("Hello <@{}> :)\n\nThe setting `{}` does not exist!", interaction.user.id, key),
+                };
+                let val = match value.map(parse_bool) {
+                    Some(Some(r)) => r,
+                    Some(None) => return invalid("`on` or `off`"),
+                    None => default,
+                };
+                if let Some(switch) = switch(&mut config, key) {
+                    *switch = val;
+                }
+            }
+
+            match crate::guilds::save(config).await {
+                true => Ok(()),
+                false => Err("The database is not available.".to_string()),
+            }
+        }
+    };
+
+    if let Err(e) = result {
+        return format!(
+            "Hello <@{}> :)\n\nThe setting `{}` could not be changed!\n```\n{}\n```",
+            interaction.user.id,
+            key,
+            e,
+        );
+    }
+
+    let config = crate::guilds::get(guild_id).await;
+    // Roles which are not synced anymore would never be removed.
+    let roles = match key {
+        "badge_roles" if !config.badge_roles_enabled => crate::roles::badge_roles(guild_id)
+            .await
+            .into_iter()
+            .filter_map(|badge_role| badge_role.role.parse::<u64>().ok())
+            .collect(),
+        "age_roles" if !config.age_roles_enabled => config.age_roles.iter().map(|age_role| age_role.get_role_id()).collect(),
+        "presence_role" if !config.presence_role_enabled => vec![config.presence_role_id],
+        "presence_role_id" if previous.presence_role_id != config.presence_role_id => vec![previous.presence_role_id],
+        _ => Vec::new(),
+    };
+    let roles = roles
+        .into_iter()
+        .filter(|role_id| *role_id >= crate::LOWEST_ID)
+        .map(RoleId::new)
+        .collect();
+    crate::roles::strip_from_members(http, guild_id, roles);
+    format!(
+        "Hello <@{}> :)\n\n`{}` is now {}!",
+        interaction.user.id,
+        key,
+        describe(&crate::settings(), &config, key),
+    )
+}
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    crate::check_admin!(interaction);
+    let guild_id = crate::require_guild!(interaction);
+
+    let options = interaction.data.options();
+    let (name, sub) = match options.first() {
+        Some(ResolvedOption { name, value: ResolvedValue::SubCommand(sub), .. }) => (*name, sub),
+        _ => return "Oops!".into(),
+    };
+    let (mut key, mut value) = (None, None);
+    for option in sub {
+        match (option.name, &option.value) {
+            ("key", ResolvedValue::String(val)) => key = Some(*val),
+            ("value", ResolvedValue::String(val)) => value = Some(*val),
+            _ => {}
+        }
+    }
+
+    match (name, key) {
+        ("view", _) => view(interaction, guild_id).await,
+        ("set", Some(key)) if value.is_some() => change(http, interaction, guild_id, key, value).await,
+        ("reset", Some(key)) => change(http, interaction, guild_id, key, None).await,
+        _ => format!("Hello <@{}> :)\n\nThe setting or value is missing!", interaction.user.id),
+    }
+}
+
+fn key_option() -> serenity::all::CreateCommandOption {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    let mut option = CreateCommandOption::new(CommandOptionType::String, "key", "The setting")
+        .required(true);
+    for key in KEYS {
+        option = option.add_string_choice(key, key);
+    }
+    option
+}
+
+pub fn register() -> CreateCommand {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    CreateCommand::new("config")
+        .description("View and change the configuration of this bot")
+        .add_option(CreateCommandOption::new(CommandOptionType::SubCommand, "view", "Show all settings"))
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "set", "Change a setting")
+                .add_sub_option(key_option())
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::String, "value", "The new value, e.g. 300, on, approval, #mod-log or @Online")
+                        .required(true),
+                ),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::SubCommand, "reset", "Change a setting back to its default")
+                .add_sub_option(key_option()),
+        )
+}
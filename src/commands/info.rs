@@ -6,38 +6,24 @@ use serenity::all::{
 };
 
 pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
-    if interaction.data.options.is_empty() {
-        return format!(
-            "Hello <@{}> :)\n\nThe username is missing!",
-            interaction.user.id,
-        );
-    }
-
-    let habbo = interaction.data.options[0].value.as_str().unwrap();
-
-    let url = format!("{}{}", crate::LOOKUP_URL, habbo);
-
-    let (req_status, req_result) = crate::helper::reqwest(&url, |response| async {
-        let text = response.text().await.unwrap_or_default();
-        serde_json::from_str::<serde_json::Value>(&text).unwrap_or_default()
-    })
-    .await;
-
-    if !req_status {
-        return format!("Hello <@{}> :)\n\nThe Habbo Hotel:Origins request has failed! Please try again later!", interaction.user.id);
-    }
-
-    let raw_res_value = req_result.unwrap().await;
-    if let Some(res_error) = raw_res_value.get("error") {
-        return format!(
-            "Hello <@{}> :)\n\nThe Habbo \"{}\" does not exist or the profile has been set to private!\n\n**error:**\n`{}`",
-            interaction.user.id,
-            habbo,
-            res_error.as_str().unwrap()
-        );
-    }
+    let habbo = match crate::helper::option_str(interaction, "username") {
+        Some(r) => r,
+        None => {
+            return format!(
+                "Hello <@{}> :)\n\nThe username is missing!",
+                interaction.user.id,
+            );
+        }
+    };
+    let hotel = match crate::helper::hotel(interaction) {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nThis hotel is not available!", interaction.user.id),
+    };
 
-    let res_value = serde_json::from_value::<crate::structs::Profile>(raw_res_value).unwrap_or_default();
+    let res_value = match crate::habbo_api::profile_by_name(&hotel, habbo).await {
+        Ok(r) => r,
+        Err(e) => return format!("Hello <@{}> :)\n\n{}", interaction.user.id, e.user_message(habbo)),
+    };
 
     let thumbnail = format!(
         "https://www.habbo.com/habbo-imaging/avatarimage?size=l&figure={}&size=b&direction=4&head_direction=4&crr=0&gesture=sml&frame=1",
@@ -46,8 +32,9 @@ pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
     let mut embed = CreateEmbed::new()
         .color(Colour::GOLD)
         .thumbnail(thumbnail)
-        .title(habbo)
+        .title(&*res_value.name)
         .field("id", res_value.unique_id, false)
+        .field("hotel", hotel.get_name(), false)
         .field("figure", res_value.figure_string, false)
         .field("motto", res_value.motto, false)
         .field("online", res_value.online.to_string(), false)
@@ -86,4 +73,5 @@ pub fn register() -> CreateCommand {
             )
             .required(true),
         )
+        .add_option(crate::helper::hotel_option())
 }
@@ -1,52 +1,162 @@
+use std::sync::Arc;
+
 use serenity::{
-    all::{CommandDataOptionValue, CommandInteraction},
+    all::{CommandDataOptionValue, CommandInteraction, EditRole, GuildId, Http, Role, RoleId},
     builder::CreateCommand,
 };
 
-pub async fn run(interaction: &CommandInteraction) -> String {
-    if interaction.data.options.is_empty() {
+/// Name of the role created if no role has been selected.
+const ROLE_NAME: &str = "Verified";
+
+/// Returns the selected role, an existing role named `ROLE_NAME` or a new one.
+async fn role(http: &Arc<Http>, guild_id: GuildId, roles: &[Role], role_id: Option<RoleId>) -> Result<Role, String> {
+    if let Some(role_id) = role_id {
+        return roles
+            .iter()
+            .find(|role| role.id == role_id)
+            .cloned()
+            .ok_or_else(|| "The role does not exist!".to_string());
+    }
+
+    if let Some(role) = roles.iter().find(|role| role.name == ROLE_NAME && !role.managed) {
+        return Ok(role.clone());
+    }
+
+    guild_id
+        .create_role(http, EditRole::new().name(ROLE_NAME))
+        .await
+        .map_err(|e| {
+            error!("Cannot create the role `{}`: {}", ROLE_NAME, e);
+            format!("The role `{}` could not be created! Please give me the permission to manage roles.", ROLE_NAME)
+        })
+}
+
+/// Gives all members with the role `from` the role `to` instead.
+/// Returns how many members have been migrated and how many failed.
+async fn migrate(http: &Arc<Http>, guild_id: GuildId, from: RoleId, to: RoleId) -> serenity::Result<(usize, usize)> {
+    let (mut migrated, mut failed) = (0, 0);
+    let mut after = None;
+    loop {
+        let members = http.get_guild_members(guild_id, Some(1000), after).await?;
+        let last = match members.last() {
+            Some(r) => r.user.id,
+            None => break,
+        };
+
+        for member in members.iter().filter(|member| member.roles.contains(&from)) {
+            let user_id = member.user.id;
+            let result = match http.add_member_role(guild_id, user_id, to, Some("/init force")).await {
+                Ok(_) => http.remove_member_role(guild_id, user_id, from, Some("/init force")).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(_) => migrated += 1,
+                Err(e) => {
+                    error!("Cannot migrate the verify role of the Discord user {}: {}", user_id, e);
+                    failed += 1;
+                }
+            }
+        }
+
+        after = Some(last.get());
+    }
+    Ok((migrated, failed))
+}
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    crate::check_admin!(interaction);
+    let guild_id = crate::require_guild!(interaction);
+
+    let (mut role_id, mut force) = (None, false);
+    for option in &interaction.data.options {
+        match (option.name.as_str(), &option.value) {
+            ("role", CommandDataOptionValue::Role(val)) => role_id = Some(*val),
+            ("force", CommandDataOptionValue::Boolean(val)) => force = *val,
+            _ => {}
+        }
+    }
+
+    let roles = match http.get_guild_roles(guild_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+        }
+    };
+
+    // The role may have been deleted since, then the bot can be initialized again.
+    let mut config = crate::guilds::get(guild_id).await;
+    let old_role = roles
+        .iter()
+        .find(|role| role.id.get() == config.verify_role_id)
+        .map(|role| role.id);
+    if old_role.is_some() && !force {
         return format!(
-            "Hello <@{}> :)\n\nThe role is missing!",
+            "Hello <@{}> :)\n\nThis bot has already been initialized with the role <@&{}>! Use `/init force:true` to switch to another role.",
             interaction.user.id,
+            config.verify_role_id,
         );
     }
 
-    let member = interaction.member.as_ref().unwrap();
-    let permissions = member.permissions.unwrap();
-    if !permissions.administrator() {
+    let role = match role(http, guild_id, &roles, role_id).await {
+        Ok(r) => r,
+        Err(e) => return format!("Hello <@{}> :)\n\n{}", interaction.user.id, e),
+    };
+    if old_role == Some(role.id) {
+        return format!("Hello <@{}> :)\n\nThe role <@&{}> is already selected!", interaction.user.id, role.id);
+    }
+    if role.managed || role.id.get() == guild_id.get() {
+        return format!("Hello <@{}> :)\n\nThe role <@&{}> cannot be given to members!", interaction.user.id, role.id);
+    }
+
+    // Bots can only give roles below their highest role.
+    if crate::roles::top_position(http, guild_id).await.is_none_or(|top| role.position >= top) {
         return format!(
-            "Hello <@{}> :)\n\nYou are not allowed to execute this command!",
-            interaction.user.id
+            "Hello <@{}> :)\n\nMy highest role must be above <@&{}>, otherwise I cannot give it to members! Please move my role up in the server settings and execute the command `/init` again.",
+            interaction.user.id,
+            role.id,
         );
     }
 
-    if crate::settings().get_guild().get_verify_role_id() >= crate::LOWEST_ID {
+    config.verify_role_id = role.id.get();
+    if !crate::guilds::save(config).await {
         return format!(
-            "Hello <@{}> :)\n\nThis bot has already been initialized!",
-            interaction.user.id
+            "Hello <@{}> :)\n\nSomething went wrong! Please try again later!",
+            interaction.user.id,
         );
     }
 
-    let role_id = match &interaction.data.options.first().unwrap().value {
-        CommandDataOptionValue::Role(val) => val,
-        _ => {
-            return format!(
-                "Hello <@{}> :)\n\nNot a valid role!",
-                interaction.user.id,
-            );
-        }
+    let old_role = match old_role {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nRole selected: <@&{}>", interaction.user.id, role.id),
     };
 
-    unsafe {
-        crate::settings().as_mut().get_guild_mut().set_verify_role_id(role_id.get());
-    };
-    crate::settings().save();
-
-    format!(
-        "Hello <@{}> :)\n\nRole selected: <@&{}>",
-        interaction.user.id,
-        role_id.get()
-    )
+    match migrate(http, guild_id, old_role, role.id).await {
+        Ok((migrated, 0)) => format!(
+            "Hello <@{}> :)\n\nRole selected: <@&{}>\n\n{} member(s) have been moved from <@&{}> to the new role.",
+            interaction.user.id,
+            role.id,
+            migrated,
+            old_role,
+        ),
+        Ok((migrated, failed)) => format!(
+            "Hello <@{}> :)\n\nRole selected: <@&{}>\n\n{} member(s) have been moved from <@&{}> to the new role, {} could not be moved.",
+            interaction.user.id,
+            role.id,
+            migrated,
+            old_role,
+            failed,
+        ),
+        Err(e) => {
+            error!("Cannot load the members of the guild {}: {}", guild_id, e);
+            format!(
+                "Hello <@{}> :)\n\nRole selected: <@&{}>\n\nThe members with the role <@&{}> could not be loaded, please move them manually! Make sure the Server Members Intent is enabled for the bot.",
+                interaction.user.id,
+                role.id,
+                old_role,
+            )
+        }
+    }
 }
 
 pub fn register() -> CreateCommand {
@@ -58,7 +168,15 @@ pub fn register() -> CreateCommand {
             CreateCommandOption::new(
                 CommandOptionType::Role,
                 "role",
-                "Select or create a role",
+                "Select a role, a role named Verified is created otherwise",
+            )
+            .required(false),
+        )
+        .add_option(
+            CreateCommandOption::new(
+                CommandOptionType::Boolean,
+                "force",
+                "Switch the role and move all members to the new role",
             )
             .required(false),
         )
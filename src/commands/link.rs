@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, Http},
+    builder::CreateCommand,
+};
+
+use crate::{
+    commands::verify::denied_msg,
+    habbo_api::HabboApiError,
+    locks::{Key, Lock},
+};
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    crate::check_admin!(interaction);
+    let guild_id = crate::require_guild!(interaction);
+    let config = crate::guilds::get(guild_id).await;
+    crate::check_role_available!(http, guild_id, config, interaction.user.id.get());
+
+    let mut user_id = None;
+    for option in &interaction.data.options {
+        if let ("user", CommandDataOptionValue::User(val)) = (option.name.as_str(), &option.value) {
+            user_id = Some(*val);
+        }
+    }
+    let habbo = crate::helper::option_str(interaction, "habbo").map(crate::helper::normalize_habbo);
+    let (user_id, habbo) = match (user_id, habbo) {
+        (Some(user_id), Some(habbo)) if !habbo.is_empty() => (user_id, habbo),
+        _ => return format!("Hello <@{}> :)\n\nThe user or Habbo is missing!", interaction.user.id),
+    };
+    let reason = match crate::helper::option_str(interaction, "reason").map(str::trim) {
+        Some(r) if !r.is_empty() => r,
+        _ => return format!("Hello <@{}> :)\n\nThe reason is missing!", interaction.user.id),
+    };
+    let hotel = match crate::helper::hotel(interaction) {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nThis hotel is not available!", interaction.user.id),
+    };
+
+    // A running verification of the user or the Habbo would be superseded by the link.
+    let mut lock = match Lock::acquire(vec![Key::User(user_id), Key::habbo(hotel.get_id(), &habbo)]) {
+        Ok(r) => r,
+        Err(denied) => return denied_msg(interaction.user.id, user_id, &habbo, denied),
+    };
+
+    // A Habbo whose profile is temporarily broken is linked by name, `jobs::rename` adds the unique id later.
+    let profile = match crate::habbo_api::profile_by_name(&hotel, &habbo).await {
+        Ok(r) => Some(r),
+        Err(e @ HabboApiError::NotFound) => return format!("Hello <@{}> :)\n\n{}", interaction.user.id, e.user_message(&habbo)),
+        Err(e) => {
+            warn!("Cannot retrieve the Habbo `{}`, linking it by name: {}", habbo, e);
+            None
+        }
+    };
+    let (name, unique_id) = match &profile {
+        Some(profile) => (&*profile.name, &*profile.unique_id),
+        None => (habbo.as_str(), ""),
+    };
+    // The same Habbo could also be requested by another name.
+    if !unique_id.is_empty() {
+        if let Err(denied) = lock.extend(vec![Key::habbo_id(hotel.get_id(), unique_id)]) {
+            return denied_msg(interaction.user.id, user_id, &habbo, denied);
+        }
+    }
+
+    let linked = crate::accounts::verified(guild_id, user_id).await.unwrap_or_default();
+    if crate::accounts::is_linked(&linked, hotel.get_id(), name, unique_id) {
+        return format!(
+            "Hello <@{}> :)\n\nThe user <@{}> has already linked the Habbo `{}`!",
+            interaction.user.id,
+            user_id,
+            name,
+        );
+    }
+
+    let owners = crate::conflicts::owners(guild_id, user_id, hotel.get_id(), unique_id, name).await;
+    if let Some(owner) = owners.first() {
+        return format!(
+            "Hello <@{}> :)\n\nThe Habbo `{}` is already linked to <@{}>! Use the command `/unlink` first.",
+            interaction.user.id,
+            name,
+            owner.id,
+        );
+    }
+
+    let why = format!("linked by the moderator {}: {}", interaction.user.id, reason);
+    if !crate::commands::verify::add(http, guild_id, user_id, hotel.get_id(), name, unique_id, &why).await
+        || !crate::commands::verify::complete(http, guild_id, user_id, hotel.get_id(), unique_id, profile.as_ref(), &why).await
+    {
+        return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+    }
+
+    crate::helper::dm(http, user_id, format!(
+        "Hello <@{}> :)\n\nA moderator linked you to the Habbo `{}` on {}!",
+        user_id,
+        name,
+        hotel.get_name(),
+    )).await;
+    crate::helper::log(http, guild_id, format!(
+        "<@{}> linked <@{}> to the Habbo `{}` on {}: {}",
+        interaction.user.id,
+        user_id,
+        name,
+        hotel.get_name(),
+        reason,
+    )).await;
+
+    let note = match profile {
+        Some(_) => "",
+        None => "\n\nThe profile of the Habbo could not be retrieved, so it has been linked by name. Its roles are given once the profile is available again.",
+    };
+    format!(
+        "Hello <@{}> :)\n\n<@{}> has been linked to the Habbo `{}` on {}!{}",
+        interaction.user.id,
+        user_id,
+        name,
+        hotel.get_name(),
+        note,
+    )
+}
+
+pub fn register() -> CreateCommand {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    CreateCommand::new("link")
+        .description("Link a user to a Habbo without a verification")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", "The user to link")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "habbo", "The name of the Habbo")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "reason", "Why the user is linked")
+                .required(true),
+        )
+        .add_option(crate::helper::hotel_option())
+}
@@ -1,5 +1,11 @@
+pub mod accounts;
+pub mod agerole;
+pub mod badgeroles;
 pub mod check;
+pub mod config;
 pub mod init;
+pub mod link;
 pub mod info;
 pub mod reset;
+pub mod unlink;
 pub mod verify;
@@ -5,21 +5,20 @@ use serenity::{
     builder::CreateCommand,
 };
 
-use crate::structs::VerifiedUser;
-
 pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
-    crate::check_role_available!(interaction.user.id.get());
+    let guild_id = crate::require_guild!(interaction);
+    crate::check_role_available!(crate::guilds::get(guild_id).await, interaction.user.id.get());
 
-    let coll = crate::mongo::get_coll::<VerifiedUser>("verified_users");
     let query = doc! {
         "id": interaction.user.id.to_string(),
+        "guild": guild_id.to_string(),
     };
-    let _ = coll.delete_many(query).await;
+    if let Err(e) = crate::links::reset(http, query, "reset by the user").await {
+        error!("{}", e);
+    }
 
-    let settings = crate::settings();
-    let guild_id = settings.get_guild().get_id().into();
-    let role_id = settings.get_guild().get_verify_role_id().into();
-    let _ = http.remove_member_role(guild_id, interaction.user.id, role_id, None).await;
+    crate::roles::strip_all(http, guild_id, interaction.user.id).await;
+    crate::nicknames::clear(http, guild_id, interaction.user.id).await;
 
     format!(
         "Hello <@{}> :)\n\nAll your data has been deleted and roles removed!",
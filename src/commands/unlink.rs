@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, Http},
+    builder::CreateCommand,
+};
+
+use crate::structs::VerificationState;
+
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    crate::check_admin!(interaction);
+    let guild_id = crate::require_guild!(interaction);
+
+    let mut user_id = None;
+    for option in &interaction.data.options {
+        if let ("user", CommandDataOptionValue::User(val)) = (option.name.as_str(), &option.value) {
+            user_id = Some(*val);
+        }
+    }
+    let user_id = match user_id {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nThe user is missing!", interaction.user.id),
+    };
+    let reason = match crate::helper::option_str(interaction, "reason").map(str::trim) {
+        Some(r) if !r.is_empty() => r,
+        _ => return format!("Hello <@{}> :)\n\nThe reason is missing!", interaction.user.id),
+    };
+
+    let links = match crate::accounts::verified(guild_id, user_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+        }
+    };
+
+    // Without a Habbo, all Habbos of the user are unlinked.
+    let hotel = crate::helper::option_str(interaction, "hotel");
+    let links = match crate::helper::option_str(interaction, "habbo") {
+        Some(habbo) => crate::commands::accounts::find(&links, habbo, hotel),
+        None => links.iter().collect(),
+    };
+    if links.is_empty() {
+        return format!("Hello <@{}> :)\n\nThe user <@{}> has not linked this Habbo!", interaction.user.id, user_id);
+    }
+
+    let why = format!("unlinked by the moderator {}: {}", interaction.user.id, reason);
+    let set = doc! {
+        "flagged_at": null,
+        "flag_reason": null,
+    };
+    let mut unlinked = Vec::new();
+    for link in links {
+        match crate::links::transition(http, crate::links::filter(link), VerificationState::Revoked, &why, set.clone()).await {
+            Ok(_) => unlinked.push(format!("`{}`", link.habbo)),
+            Err(e) => error!("Cannot unlink the Habbo `{}` of the Discord user {}: {}", link.habbo, user_id, e),
+        }
+    }
+    if unlinked.is_empty() {
+        return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+    }
+    crate::accounts::removed(http, guild_id, user_id).await;
+
+    let habbos = unlinked.join(", ");
+    crate::helper::dm(http, user_id, format!(
+        "Hello <@{}> :)\n\nA moderator removed your verification as Habbo {}: {}",
+        user_id,
+        habbos,
+        reason,
+    )).await;
+    crate::helper::log(http, guild_id, format!(
+        "<@{}> unlinked the Habbo {} of <@{}>: {}",
+        interaction.user.id,
+        habbos,
+        user_id,
+        reason,
+    )).await;
+
+    format!(
+        "Hello <@{}> :)\n\nThe Habbo {} of <@{}> has been unlinked!",
+        interaction.user.id,
+        habbos,
+        user_id,
+    )
+}
+
+pub fn register() -> CreateCommand {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
+
+    CreateCommand::new("unlink")
+        .description("Remove the verification of a user")
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", "The user to unlink")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "reason", "Why the user is unlinked")
+                .required(true),
+        )
+        .add_option(
+            CreateCommandOption::new(CommandOptionType::String, "habbo", "Only unlink this Habbo")
+                .required(false),
+        )
+        .add_option(crate::helper::hotel_option())
+}
@@ -1,178 +1,582 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use mongodb::Collection;
 use rand::Rng;
 use serenity::{
     all::{
-        CommandInteraction, GuildId, Http, RoleId, UserId,
+        ButtonStyle, CommandInteraction, ComponentInteraction, CreateActionRow, CreateButton,
+        GuildId, Http, UserId,
     },
     builder::CreateCommand,
     futures::TryStreamExt,
 };
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    habbo_api, links,
+    locks::{Denied, Key, Lock},
+    structs::{Claim, ConflictPolicy, PendingVerification, Profile, VerificationState},
+};
+
+/// Actions a user can trigger via the buttons of a pending verification.
+enum Action {
+    Check,
+    Cancel,
+}
+
+fn pending() -> &'static Mutex<HashMap<UserId, UnboundedSender<Action>>> {
+    use std::sync::OnceLock;
+    static VAL: OnceLock<Mutex<HashMap<UserId, UnboundedSender<Action>>>> = OnceLock::new();
+    VAL.get_or_init(Default::default)
+}
 
-use crate::structs::VerifiedUser;
+/// Registers a pending verification and unregisters it again when dropped.
+struct PendingGuard(UserId);
 
-async fn check(interaction: &CommandInteraction, coll: &Collection<VerifiedUser>) -> bool {
+impl PendingGuard {
+    fn new(user_id: UserId) -> (Self, UnboundedReceiver<Action>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        pending().lock().unwrap().insert(user_id, tx);
+        (Self(user_id), rx)
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        pending().lock().unwrap().remove(&self.0);
+    }
+}
+
+/// The buttons carry the id of the user who started the verification, see `component`.
+fn buttons(user_id: UserId) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("verify:check:{}", user_id))
+            .label("I've changed my motto — check now")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("verify:cancel:{}", user_id))
+            .label("Cancel")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Moves the link of the user to the Habbo into the state `pending`, creating it if needed.
+/// Habbos whose profile cannot be retrieved are linked by name, `unique_id` is empty then.
+pub async fn add(
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    user_id: UserId,
+    hotel: &str,
+    habbo: &str,
+    unique_id: &str,
+    reason: &str,
+) -> bool {
+    let (id, guild) = (user_id.to_string(), guild_id.to_string());
+
+    // A verification of an earlier attempt may still be pending.
     let query = doc! {
-        "id": interaction.user.id.to_string(),
-        "verified": true,
+        "id": &id,
+        "guild": &guild,
+        "state": VerificationState::Pending.as_str(),
+    };
+    let _ = links::transition(http, query, VerificationState::Expired, "superseded by a new verification", doc! {}).await;
+
+    // The Habbo may have been linked by the user before.
+    let mut query = doc! {
+        "id": &id,
+        "guild": &guild,
+        "hotel": hotel,
     };
-    match coll.find_one(query).await {
-        Ok(r) => r.is_some(),
-        Err(_) => false,
+    match unique_id.is_empty() {
+        true => query.insert("habbo", crate::helper::normalize_habbo(habbo)),
+        false => query.insert("unique_id", unique_id),
+    };
+    let result = match links::coll().find_one(query.clone()).collation(crate::mongo::habbo_collation()).await {
+        Ok(Some(_)) => {
+            let set = doc! {
+                "habbo": crate::helper::normalize_habbo(habbo),
+            };
+            links::transition(http, query, VerificationState::Pending, reason, set).await
+        }
+        Ok(None) => links::create(http, &id, &guild, hotel, habbo, unique_id, reason).await,
+        Err(e) => Err(e.into()),
+    };
+
+    if let Err(e) = result {
+        error!("{}", e);
+        return false;
     }
+    true
 }
 
-async fn add(habbo: &str, interaction: &CommandInteraction, coll: &Collection<VerifiedUser>) -> bool {
-    let verified_user = VerifiedUser {
-        id: interaction.user.id.to_string(),
-        habbo: habbo.into(),
-        verified: false,
+async fn add_pending(pending: &PendingVerification, coll: &Collection<PendingVerification>) -> bool {
+    let query = doc! {
+        "id": &pending.id,
     };
-    if let Err(e) = coll.insert_one(verified_user).await {
+    let options = mongodb::options::ReplaceOptions::builder().upsert(true).build();
+    if let Err(e) = coll.replace_one(query, pending).with_options(options).await {
         error!("{}", e);
         return false;
     }
     true
 }
 
-async fn delete(
-    http: &Arc<Http>,
-    habbo: &str,
-    interaction: &CommandInteraction,
-    coll: &Collection<VerifiedUser>,
-    guild_id: GuildId,
-    role_id: RoleId,
-) {
+async fn remove(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, reason: &str) {
     let query = doc! {
-        "id": {
-            // "$ne" is a filter.
-            // It will select all documents but not the one with the following id.
-            "$ne": interaction.user.id.to_string(),
-        },
-        "habbo": habbo,
-    };
-
-    // Remove roles from users.
-    if let Ok(mut users) = coll.find(query.clone()).await {
-        'userloop: while let Ok(u_o) = users.try_next().await {
-            let uid = if let Some(u) = u_o {
-                u.id.parse::<u64>().unwrap()
-            } else {
-                break 'userloop;
-            };
-            let uid = UserId::from(uid);
-            let _ = http.remove_member_role(guild_id, uid, role_id, None).await;
-        }
+        "id": user_id.to_string(),
+        "guild": guild_id.to_string(),
+        "state": VerificationState::Pending.as_str(),
+    };
+    if let Err(e) = links::transition(http, query, VerificationState::Expired, reason, doc! {}).await {
+        error!("{}", e);
     }
+}
 
-    // Delete all users from the database. 
-    let _ = coll.delete_many(query).await;
+async fn remove_pending(user_id: UserId) {
+    let coll = crate::mongo::get_coll::<PendingVerification>("pending_verifications");
+    let query = doc! {
+        "id": user_id.to_string(),
+    };
+    if let Err(e) = coll.delete_many(query).await {
+        error!("{}", e);
+    }
 }
 
-async fn update(habbo: &str, interaction: &CommandInteraction, coll: &Collection<VerifiedUser>) -> bool {
-    let (first_query, second_query) = (
-        doc! {
-            "id": interaction.user.id.to_string(),
-            "habbo": habbo,
-        },
-        doc! {
-            "$set": {
-                "verified": true,
-            }
-        }
-    );
+/// Marks the pending verification of the user as verified and gives the user the verify role.
+/// The first Habbo of the user becomes primary, then the roles from the profile are synced
+/// if a profile is given.
+pub async fn complete(
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    user_id: UserId,
+    hotel: &str,
+    unique_id: &str,
+    profile: Option<&Profile>,
+    reason: &str,
+) -> bool {
+    // Only one link of the user can be pending.
+    let mut query = doc! {
+        "id": user_id.to_string(),
+        "guild": guild_id.to_string(),
+        "hotel": hotel,
+        "state": VerificationState::Pending.as_str(),
+    };
+    if !unique_id.is_empty() {
+        query.insert("unique_id", unique_id);
+    }
 
-    if let Err(e) = coll.update_one(first_query, second_query).await {
+    let primary = !crate::accounts::has_primary(guild_id, user_id).await;
+    let set = doc! {
+        "member_since": profile.and_then(Profile::member_since),
+        "primary": primary,
+    };
+    if let Err(e) = links::transition(http, query, VerificationState::Verified, reason, set).await {
         error!("{}", e);
         return false;
     }
 
+    let role_id = crate::guilds::get(guild_id).await.verify_role_id.into();
+    let _ = http
+        .add_member_role(guild_id, user_id, role_id, None)
+        .await;
+
+    if let Some(profile) = profile.filter(|_| primary) {
+        crate::roles::sync_badges(http, guild_id, user_id, profile).await;
+        if let Some(member_since) = profile.member_since() {
+            crate::roles::sync_age(http, guild_id, user_id, member_since).await;
+        }
+        crate::roles::sync_presence(http, guild_id, user_id, profile.online).await;
+    }
+    crate::nicknames::sync(http, guild_id, user_id).await;
     true
 }
 
-pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
-    crate::check_role_available!(http, interaction.user.id.get());
+/// Replaces every `#` of `format` by a random letter or digit.
+fn code(format: &str) -> String {
+    let mut rng = rand::thread_rng();
+    format
+        .chars()
+        .map(|c| match c {
+            '#' => char::from(rng.sample(rand::distributions::Alphanumeric)),
+            c => c,
+        })
+        .collect()
+}
 
-    if interaction.data.options.is_empty() {
-        return format!(
-            "Hello <@{}> :)\n\nThe username is missing!",
-            interaction.user.id,
+fn habbo_key(pending: &PendingVerification) -> Key {
+    match pending.unique_id.is_empty() {
+        true => Key::habbo(&pending.hotel, &pending.habbo),
+        false => Key::habbo_id(&pending.hotel, &pending.unique_id),
+    }
+}
+
+/// The reply if the lock for a verification of `user_id` is denied. `reply_to` is the user
+/// or the moderator linking them with `/link`.
+pub(crate) fn denied_msg(reply_to: UserId, user_id: UserId, habbo: &str, denied: Denied) -> String {
+    match denied {
+        Denied::Locked(Key::User(_)) if reply_to == user_id => format!(
+            "Hello <@{}> :)\n\nYou already have a pending verification! Please finish or cancel it first.",
+            reply_to,
+        ),
+        Denied::Locked(Key::User(_)) => format!(
+            "Hello <@{}> :)\n\nThe user <@{}> is verifying a Habbo right now! Please try again later.",
+            reply_to,
+            user_id,
+        ),
+        Denied::Locked(Key::Habbo(_)) => format!(
+            "Hello <@{}> :)\n\nThe Habbo \"{}\" is being verified by someone else right now! Please try again later.",
+            reply_to,
+            habbo,
+        ),
+        Denied::Cooldown(Key::User(_), left) if reply_to == user_id => format!(
+            "Hello <@{}> :)\n\nYour last verification failed. Please try again in {} seconds!",
+            reply_to,
+            left.as_secs().max(1),
+        ),
+        Denied::Cooldown(Key::User(_), left) => format!(
+            "Hello <@{}> :)\n\nThe last verification of <@{}> failed. Please try again in {} seconds!",
+            reply_to,
+            user_id,
+            left.as_secs().max(1),
+        ),
+        Denied::Cooldown(Key::Habbo(_), left) => format!(
+            "Hello <@{}> :)\n\nThe last verification of the Habbo \"{}\" failed. Please try again in {} seconds!",
+            reply_to,
+            habbo,
+            left.as_secs().max(1),
+        ),
+    }
+}
+
+/// Polls the Habbo profile until the motto matches the code, the user cancels the verification
+/// or the verification expires. The pending verification is removed in every case.
+async fn poll(http: &Arc<Http>, pending: &PendingVerification, lock: &Lock) -> String {
+    let user_id = UserId::from(pending.id.parse::<u64>().unwrap());
+    let (habbo, verify_code) = (&*pending.habbo, &*pending.code);
+    let guild_id = match pending.guild_id() {
+        Some(r) => r,
+        None => {
+            remove_pending(user_id).await;
+            return format!("Hello <@{}> :)\n\nThis server is not available anymore!", user_id);
+        }
+    };
+    // The same settings are used until the verification is done, even if they are reloaded.
+    let settings = crate::settings();
+    let hotel = match settings.get_hotel(&pending.hotel) {
+        Some(r) => r,
+        None => {
+            remove(http, guild_id, user_id, "the hotel has been removed").await;
+            remove_pending(user_id).await;
+            return format!("Hello <@{}> :)\n\nThe hotel `{}` is not available anymore!", user_id, pending.hotel);
+        }
+    };
+
+    let window = settings.get_verification().get_window();
+    let interval = Duration::from_secs(settings.get_verification().get_interval().max(1));
+    let left_millis = pending.expires_at.timestamp_millis() - bson::DateTime::now().timestamp_millis();
+    let deadline = Instant::now() + Duration::from_millis(left_millis.max(0) as u64);
+
+    let (_guard, mut actions) = PendingGuard::new(user_id);
+
+    let mut attempt = 0;
+    let mut verified = None;
+    let mut request_error = None;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let reply_msg = format!(
+            "Hello <@{}> :)\n\nTo verify yourself, change your motto to `{}` and change it again after a successful verification!\n\nTime left: **{}** seconds | Attempt: **{}**",
+            user_id,
+            verify_code,
+            left.as_secs(),
+            attempt,
         );
+        if let Err(e) = crate::helper::edit_token(http, &pending.token, reply_msg, buttons(user_id)).await {
+            error!("Cannot edit respond: {}", e);
+        }
+
+        if left.is_zero() {
+            break;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(interval.min(left)) => {}
+            action = actions.recv() => {
+                if !matches!(action, Some(Action::Check)) {
+                    remove(http, guild_id, user_id, "cancelled by the user").await;
+                    remove_pending(user_id).await;
+                    return format!("Hello <@{}> :)\n\nThe verification has been cancelled!", user_id);
+                }
+            }
+        }
+        attempt += 1;
+
+        // Retrieve Habbo profile data
+        let result = match pending.unique_id.is_empty() {
+            true => habbo_api::profile_by_name(hotel, habbo).await,
+            // Renaming the Habbo during the verification does not matter.
+            false => habbo_api::profile_by_id(hotel, &pending.unique_id).await,
+        };
+        match result {
+            Ok(profile) => {
+                request_error = None;
+                if *profile.motto == *verify_code {
+                    verified = Some(profile);
+                    break;
+                }
+            }
+            Err(e) if e.is_transient() => request_error = Some(e),
+            Err(e) => {
+                remove(http, guild_id, user_id, &format!("Habbo lookup failed: {}", e)).await;
+                remove_pending(user_id).await;
+                lock.cooldown();
+                return format!("Hello <@{}> :)\n\n{}", user_id, e.user_message(habbo));
+            }
+        }
     }
 
-    let habbo = interaction.data.options[0].value.as_str().unwrap();
-    let coll = crate::mongo::get_coll("verified_users");
+    remove_pending(user_id).await;
+
+    let profile = match verified {
+        Some(r) => r,
+        None => {
+            if let Some(e) = request_error {
+                remove(http, guild_id, user_id, &format!("Habbo lookup failed: {}", e)).await;
+                return format!("Hello <@{}> :)\n\n{}", user_id, e.user_message(habbo));
+            }
+
+            remove(http, guild_id, user_id, "motto was not changed in time").await;
+            lock.cooldown();
+
+            return format!(
+                "Hello <@{}> :)\n\nThe motto of the Habbo \"{}\" was not changed to `{}` within {} seconds. Verification failed!",
+                user_id,
+                habbo,
+                verify_code,
+                window,
+            );
+        }
+    };
+
+    let config = crate::guilds::get(guild_id).await;
+    let owners = crate::conflicts::owners(guild_id, user_id, &pending.hotel, &pending.unique_id, habbo).await;
+    // Nothing happens until `completed` is awaited, a transfer awaits it once the previous owners are gone.
+    let completed = complete(http, guild_id, user_id, &pending.hotel, &pending.unique_id, Some(&profile), "motto matched the verification code");
+    let completed = match config.conflict_policy {
+        _ if owners.is_empty() => completed.await,
+        ConflictPolicy::Transfer => {
+            let reason = format!("Habbo has been verified by the Discord user {}", user_id);
+            crate::conflicts::transfer(http, guild_id, &owners, &reason, completed).await
+        }
+        policy => {
+            if policy == ConflictPolicy::Approval {
+                let claim = Claim {
+                    id: bson::oid::ObjectId::new(),
+                    guild: pending.guild.clone(),
+                    user: pending.id.clone(),
+                    habbo: pending.habbo.clone(),
+                    unique_id: pending.unique_id.clone(),
+                    hotel: pending.hotel.clone(),
+                    owners: owners.into_iter().map(|owner| owner.id).collect(),
+                    created_at: bson::DateTime::now(),
+                    reviewing: false,
+                };
+                if crate::conflicts::open(http, &config, claim).await {
+                    return format!(
+                        "Hello <@{}> :)\n\nYour motto matched, but the Habbo \"{}\" is already linked to another member. A moderator has to approve the transfer, you will receive a direct message!",
+                        user_id,
+                        habbo,
+                    );
+                }
+                // Without a channel for moderators nobody could approve the claim.
+            }
 
-    if check(interaction, &coll).await {
-        return format!("Hello <@{}> :)\n\nYou are already verified! Use the command `/reset` to delete all your data from our database, remove all your roles and verify yourself again.", interaction.user.id);
+            remove(http, guild_id, user_id, "Habbo is already linked to another member").await;
+            return format!(
+                "Hello <@{}> :)\n\nThe Habbo \"{}\" is already linked to another member! Please contact a moderator if this is your Habbo.",
+                user_id,
+                habbo,
+            );
+        }
+    };
+
+    if !completed {
+        return format!("Hello <@{}> :)\n\nUnfortunately we could not update your data in our database! Please try again later!", user_id);
     }
 
-    let verify_code: String = rand::thread_rng()
-        .sample_iter(&rand::distributions::Alphanumeric)
-        .take(5)
-        .map(char::from)
-        .collect();
+    format!(
+        "Hello <@{}> :)\n\nCongratulations! You have successfully verified yourself!",
+        user_id
+    )
+}
+
+/// Resumes all pending verifications after a restart of the bot.
+/// Verifications which expired while the bot was offline are removed and the users are notified.
+pub async fn resume(http: &Arc<Http>) {
+    use std::sync::atomic::{AtomicBool, Ordering};
 
-    if !add(habbo, interaction, &coll).await {
-        return format!("Hello <@{}> :)\n\nUnfortunately we could not add you to our database! Please try again later!", interaction.user.id);
-    } else {
-        let reply_msg = format!("Hello <@{}> :)\n\nTo verify yourself, change your motto to `{}` within the next 45 seconds and change it again after a successful verification!", interaction.user.id, verify_code);
-        crate::helper::edit_reply(&http, reply_msg, interaction).await;
+    // `ready` is called again after every reconnect.
+    static RESUMED: AtomicBool = AtomicBool::new(false);
+    if RESUMED.swap(true, Ordering::SeqCst) {
+        return;
     }
 
-    // Wait 45 seconds ...
-    tokio::time::sleep(tokio::time::Duration::from_secs(45)).await;
+    let coll = crate::mongo::get_coll::<PendingVerification>("pending_verifications");
+    let mut cursor = match coll.find(doc! {}).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load pending verifications: {}", e);
+            return;
+        }
+    };
+
+    let now = bson::DateTime::now();
+    while let Ok(Some(pending)) = cursor.try_next().await {
+        let (user_id, guild_id) = match (pending.id.parse::<u64>(), pending.guild_id()) {
+            (Ok(user_id), Some(guild_id)) => (UserId::new(user_id), guild_id),
+            _ => continue,
+        };
+
+        if pending.expires_at <= now {
+            remove(http, guild_id, user_id, "expired while the bot was offline").await;
+            remove_pending(user_id).await;
+
+            let msg = format!(
+                "Hello <@{}> :)\n\nYour verification of the Habbo \"{}\" expired while the bot was offline. Please use the command `/verify` again!",
+                user_id,
+                pending.habbo,
+            );
+            crate::helper::notify(http, user_id, &pending.token, msg).await;
+            continue;
+        }
 
-    // Retrieve Habbo profile data
-    let url = format!("{}{}", crate::LOOKUP_URL, habbo);
-    let (req_status, req_result) = crate::helper::reqwest(&url, |res| async {
-        let res_text = res.text().await.unwrap_or_default();
-        serde_json::from_str::<serde_json::Value>(&res_text).unwrap_or_default()
-    }).await;
+        let keys = vec![Key::User(user_id), habbo_key(&pending)];
+        let lock = match Lock::acquire(keys) {
+            Ok(r) => r,
+            Err(_) => continue,
+        };
 
-    if !req_status {
-        return format!("Hello <@{}> :)\n\nThe Habbo Hotel:Origins request has failed! Please try again later!", interaction.user.id);
+        let http = Arc::clone(http);
+        tokio::spawn(async move {
+            let msg = poll(&http, &pending, &lock).await;
+            crate::helper::notify(&http, user_id, &pending.token, msg).await;
+        });
     }
+}
 
-    let res_value = req_result.unwrap().await;
+pub async fn run(http: &Arc<Http>, interaction: &CommandInteraction) -> String {
+    let guild_id = crate::require_guild!(interaction);
+    let config = crate::guilds::get(guild_id).await;
+    crate::check_role_available!(http, guild_id, config, interaction.user.id.get());
 
-    if let Some(ev) = res_value.get("error") {
+    let habbo = match crate::helper::option_str(interaction, "username") {
+        Some(r) => crate::helper::normalize_habbo(r),
+        None => {
+            return format!(
+                "Hello <@{}> :)\n\nThe username is missing!",
+                interaction.user.id,
+            );
+        }
+    };
+    let hotel = match crate::helper::hotel(interaction) {
+        Some(r) => r,
+        None => return format!("Hello <@{}> :)\n\nThis hotel is not available!", interaction.user.id),
+    };
+    let mut lock = match Lock::acquire(vec![Key::User(interaction.user.id), Key::habbo(hotel.get_id(), &habbo)]) {
+        Ok(r) => r,
+        Err(denied) => return denied_msg(interaction.user.id, interaction.user.id, &habbo, denied),
+    };
+
+    let linked = match crate::accounts::verified(guild_id, interaction.user.id).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("{}", e);
+            return format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+        }
+    };
+    if linked.len() >= config.max_accounts.max(1) as usize {
         return format!(
-            "Hello <@{}> :)\n\nThe Habbo \"{}\" does not exist or the profile has been set to private!\n\n**error:**\n`{}`",
+            "Hello <@{}> :)\n\nYou have already linked {} Habbo(s), the most this server allows! Use the command `/accounts unlink` to unlink one, or `/reset` to delete all your data from our database, remove all your roles and verify yourself again.",
             interaction.user.id,
-            habbo,
-            ev.as_str().unwrap(),
+            linked.len(),
         );
     }
 
-    if res_value["motto"] != verify_code {
+    // The unique id of the Habbo never changes, unlike its name.
+    let profile = match habbo_api::profile_by_name(&hotel, &habbo).await {
+        Ok(r) => r,
+        Err(e) => {
+            if !e.is_transient() {
+                lock.cooldown();
+            }
+            return format!("Hello <@{}> :)\n\n{}", interaction.user.id, e.user_message(&habbo));
+        }
+    };
+
+    // The same Habbo could also be requested by another name.
+    if let Err(denied) = lock.extend(vec![Key::habbo_id(hotel.get_id(), &profile.unique_id)]) {
+        return denied_msg(interaction.user.id, interaction.user.id, &habbo, denied);
+    }
+
+    if crate::accounts::is_linked(&linked, hotel.get_id(), &profile.name, &profile.unique_id) {
         return format!(
-            "Hello <@{}> :)\n\nThe motto of the Habbo \"{}\" was not changed to `{}` within 45 seconds. Verification failed!",
+            "Hello <@{}> :)\n\nYou have already linked the Habbo \"{}\"! Use the command `/accounts list` to see all your Habbos.",
             interaction.user.id,
-            habbo,
-            verify_code,
+            profile.name,
         );
     }
 
     let settings = crate::settings();
-    let guild_id = settings.get_guild().get_id().into();
-    let role_id = settings.get_guild().get_verify_role_id().into();
+    let verify_code = code(settings.get_verification().get_code_format());
 
-    delete(&http, habbo, interaction, &coll, guild_id, role_id).await;
+    let window = settings.get_verification().get_window();
+    let pending = PendingVerification {
+        id: interaction.user.id.to_string(),
+        guild: guild_id.to_string(),
+        habbo: crate::helper::normalize_habbo(&profile.name).into(),
+        unique_id: profile.unique_id.clone(),
+        hotel: hotel.get_id().into(),
+        code: verify_code.into(),
+        token: interaction.token.as_str().into(),
+        expires_at: bson::DateTime::now().saturating_add_duration(Duration::from_secs(window)),
+    };
 
-    if !update(habbo, interaction, &coll).await {
-        return format!("Hello <@{}> :)\n\nUnfortunately we could not update your data in our database! Please try again later!", interaction.user.id);
+    if !add(http, guild_id, interaction.user.id, hotel.get_id(), &profile.name, &profile.unique_id, "verification started").await
+        || !add_pending(&pending, &crate::mongo::get_coll("pending_verifications")).await
+    {
+        return format!("Hello <@{}> :)\n\nUnfortunately we could not add you to our database! Please try again later!", interaction.user.id);
     }
 
-    let _ = http
-        .add_member_role(guild_id, interaction.user.id, role_id, None)
-        .await;
+    poll(http, &pending, &lock).await
+}
 
-    format!(
-        "Hello <@{}> :)\n\nCongratulations! You have successfully verified yourself!",
-        interaction.user.id
-    )
+pub async fn component(http: &Arc<Http>, action: &str, interaction: &ComponentInteraction) {
+    let (action, owner) = match action.split_once(':') {
+        Some(("check", owner)) => (Action::Check, owner),
+        Some(("cancel", owner)) => (Action::Cancel, owner),
+        _ => return crate::helper::acknowledge(http, interaction).await,
+    };
+
+    // The verification message may be visible to other members of the channel.
+    if owner != interaction.user.id.to_string() {
+        let msg = format!("Hello <@{}> :)\n\nThis is not your verification! Use the command `/verify` to start your own.", interaction.user.id);
+        return crate::helper::reply_component(http, msg, interaction).await;
+    }
+
+    let sent = match pending().lock().unwrap().get(&interaction.user.id) {
+        Some(tx) => tx.send(action).is_ok(),
+        None => false,
+    };
+
+    if !sent {
+        let msg = format!("Hello <@{}> :)\n\nThere is no pending verification!", interaction.user.id);
+        return crate::helper::reply_component(http, msg, interaction).await;
+    }
+
+    crate::helper::acknowledge(http, interaction).await;
 }
 
 pub fn register() -> CreateCommand {
@@ -188,4 +592,5 @@ pub fn register() -> CreateCommand {
             )
             .required(true),
         )
+        .add_option(crate::helper::hotel_option())
 }
@@ -0,0 +1,120 @@
+use std::{
+    sync::{Arc, Mutex, OnceLock, RwLock},
+    time::SystemTime,
+};
+
+use tokio::time::Duration;
+
+use crate::structs::Settings;
+
+/// The path of the settings file, see `cli::Args::config`.
+pub fn path() -> &'static str {
+    &crate::cli::args().config
+}
+
+/// Seconds between two checks whether the settings file has been changed.
+const WATCH_INTERVAL_SECS: u64 = 5;
+
+fn current() -> &'static RwLock<Arc<Settings>> {
+    static VAL: OnceLock<RwLock<Arc<Settings>>> = OnceLock::new();
+    VAL.get_or_init(|| RwLock::new(Arc::new(Settings::load())))
+}
+
+/// Held while the settings are replaced, so concurrent updates cannot overwrite each other.
+fn writer() -> &'static Mutex<()> {
+    static VAL: OnceLock<Mutex<()>> = OnceLock::new();
+    VAL.get_or_init(Default::default)
+}
+
+/// Returns a snapshot of the settings. A snapshot never changes, even if the settings
+/// are reloaded in the meantime, so take it once and use it until the work is done.
+pub fn get() -> Arc<Settings> {
+    Arc::clone(&current().read().unwrap())
+}
+
+/// Applies `f` to the settings file, saves it and makes it the current snapshot.
+/// The file is read again, so values from environment variables are never written to it.
+/// Nothing is written if `f` does not change anything or the new settings are invalid.
+pub fn update<F>(f: F) -> std::io::Result<()>
+where
+    F: FnOnce(&mut Settings),
+{
+    let _guard = writer().lock().unwrap();
+
+    let mut settings = Settings::read_file(path()).map_err(std::io::Error::other)?;
+    let before = serde_json::to_value(&settings)?;
+    f(&mut settings);
+    if serde_json::to_value(&settings)? == before {
+        return Ok(());
+    }
+
+    let mut applied = settings.clone();
+    applied.apply_env();
+    let problems = crate::validation::validate(&applied);
+    if !problems.is_empty() {
+        return Err(std::io::Error::other(crate::validation::report(&problems)));
+    }
+
+    settings.save(path())?;
+    *current().write().unwrap() = Arc::new(applied);
+    Ok(())
+}
+
+/// Reads the settings file again. Invalid settings are logged and the current ones are kept.
+pub fn reload() {
+    let _guard = writer().lock().unwrap();
+
+    let settings = match Settings::read(path()) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot reload the settings, the current ones are kept\n> {}", e);
+            return;
+        }
+    };
+
+    let problems = crate::validation::validate(&settings);
+    if !problems.is_empty() {
+        error!("Cannot reload the settings, the current ones are kept\n> {}", crate::validation::report(&problems));
+        return;
+    }
+
+    *current().write().unwrap() = Arc::new(settings);
+    info!("Reloaded `{}`", path());
+}
+
+fn modified() -> Option<SystemTime> {
+    std::fs::metadata(path()).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Reloads the settings whenever the settings file changes or the bot receives SIGHUP.
+/// `mongodb`, `token` and `threads` are only read at startup, changing them requires a restart.
+pub fn watch() {
+    tokio::spawn(async {
+        let mut last = modified();
+        let mut interval = tokio::time::interval(Duration::from_secs(WATCH_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let now = modified();
+            if now.is_some() && now != last {
+                last = now;
+                reload();
+            }
+        }
+    });
+
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Cannot listen for SIGHUP: {}", e);
+                return;
+            }
+        };
+        while hangup.recv().await.is_some() {
+            reload();
+        }
+    });
+}
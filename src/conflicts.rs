@@ -0,0 +1,342 @@
+use std::{future::Future, sync::Arc};
+
+use bson::Document;
+use mongodb::Collection;
+use serenity::{
+    all::{
+        ButtonStyle, ChannelId, Colour, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, GuildId, Http, UserId,
+    },
+    futures::TryStreamExt,
+};
+
+use crate::{
+    links,
+    structs::{Claim, GuildConfig, VerificationState, VerifiedUser},
+};
+
+/// Claims nobody has reviewed within a week expire, the claimant can verify the Habbo again.
+const CLAIM_TTL_MILLIS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+pub fn coll() -> Collection<Claim> {
+    crate::mongo::get_coll("claims")
+}
+
+/// Returns the other members of the guild the Habbo is verified by.
+pub async fn owners(guild_id: GuildId, user_id: UserId, hotel: &str, unique_id: &str, habbo: &str) -> Vec<VerifiedUser> {
+    let mut query = doc! {
+        "id": {
+            // "$ne" is a filter.
+            // It will select all documents but not the one with the following id.
+            "$ne": user_id.to_string(),
+        },
+        "guild": guild_id.to_string(),
+        "hotel": hotel,
+        "state": VerificationState::Verified.as_str(),
+    };
+    match unique_id.is_empty() {
+        // The Habbo is only known by name, e.g. if it has been linked with `/link` while the profile could not be retrieved.
+        true => query.insert("habbo", habbo),
+        false => query.insert("$or", vec![
+            doc! { "unique_id": unique_id },
+            // Links of older versions are only known by name.
+            doc! { "unique_id": null, "habbo": habbo },
+        ]),
+    };
+
+    match links::coll().find(query).collation(crate::mongo::habbo_collation()).await {
+        Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+        Err(e) => {
+            error!("{}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Takes the Habbo away from its previous owners and hands it over by running `complete`,
+/// which completes the pending link of the new owner. Returns whether `complete` succeeded.
+///
+/// Only one link of a Habbo can be verified, so the links of the previous owners are transferred first.
+/// If `complete` fails, they are verified again and keep their roles,
+/// otherwise their roles are removed and they are told about it.
+/// `reason` is recorded in the history of their links.
+pub async fn transfer(
+    http: &Arc<Http>,
+    guild_id: GuildId,
+    owners: &[VerifiedUser],
+    reason: &str,
+    complete: impl Future<Output = bool>,
+) -> bool {
+    let mut transferred = Vec::new();
+    for owner in owners {
+        match links::transition(http, links::filter(owner), VerificationState::Transferred, reason, doc! {}).await {
+            Ok(_) => transferred.push(owner),
+            Err(e) => error!("Cannot transfer the Habbo `{}` of the Discord user {}: {}", owner.habbo, owner.id, e),
+        }
+    }
+
+    if !complete.await {
+        for owner in transferred {
+            let query = links::filter(owner);
+            if let Err(e) = links::transition(http, query, VerificationState::Verified, "the transfer could not be completed", doc! {}).await {
+                error!("Cannot restore the Habbo `{}` of the Discord user {}: {}", owner.habbo, owner.id, e);
+            }
+        }
+        return false;
+    }
+
+    for owner in transferred {
+        let owner_id = match owner.id.parse::<u64>() {
+            Ok(r) => UserId::new(r),
+            Err(_) => continue,
+        };
+        crate::accounts::removed(http, guild_id, owner_id).await;
+
+        crate::helper::dm(http, owner_id, format!(
+            "Hello <@{}> :)\n\nYour Habbo `{}` has been verified by another Discord account, so your verification has been removed. If this was not you, please contact a moderator!",
+            owner_id,
+            owner.habbo,
+        )).await;
+        crate::helper::log(http, guild_id, format!(
+            "The Habbo `{}` of <@{}> has been transferred: {}.",
+            owner.habbo,
+            owner_id,
+            reason,
+        )).await;
+    }
+    true
+}
+
+fn buttons(claim: &Claim) -> Vec<CreateActionRow> {
+    let id = claim.id.to_hex();
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("claim:approve:{}", id))
+            .label("Approve transfer")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("claim:deny:{}", id))
+            .label("Deny")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// Posts a review card for the claim to the channel for moderators.
+/// Returns `false` if the guild has no channel for moderators or the card cannot be posted.
+pub async fn open(http: &Arc<Http>, config: &GuildConfig, claim: Claim) -> bool {
+    let channel_id = match [config.mod_channel_id, config.log_channel_id]
+        .into_iter()
+        .find(|id| *id >= crate::LOWEST_ID)
+    {
+        Some(r) => ChannelId::new(r),
+        None => return false,
+    };
+
+    if let Err(e) = coll().insert_one(&claim).await {
+        error!("{}", e);
+        return false;
+    }
+
+    let settings = crate::settings();
+    let hotel_name = settings
+        .get_hotel(&claim.hotel)
+        .map_or(&*claim.hotel, |hotel| hotel.get_name());
+    let owners = claim
+        .owners
+        .iter()
+        .map(|id| format!("<@{}>", id))
+        .collect::<Vec<_>>();
+    let embed = CreateEmbed::new()
+        .title("Claim of a verified Habbo")
+        .description("The motto of the Habbo has been changed to the verification code, but it is already linked to another member.")
+        .colour(Colour::ORANGE)
+        .field("Habbo", format!("`{}`", claim.habbo), true)
+        .field("Hotel", hotel_name, true)
+        .field("Claimed by", format!("<@{}>", claim.user), true)
+        .field("Linked to", owners.join(", "), true);
+
+    let message = CreateMessage::new().embed(embed).components(buttons(&claim));
+    if let Err(e) = channel_id.send_message(http, message).await {
+        error!("Cannot post the claim to the channel {}: {}", channel_id, e);
+        let _ = coll().delete_one(doc! { "_id": claim.id }).await;
+        return false;
+    }
+    true
+}
+
+/// Returns the result for the review card, or an error if the claim can be approved again later.
+/// Matches the pending link of the claimant. The claimant may have started another verification in the meantime.
+fn pending(claim: &Claim) -> Document {
+    doc! {
+        "id": &claim.user,
+        "guild": &claim.guild,
+        "hotel": &*claim.hotel,
+        "unique_id": &*claim.unique_id,
+        "state": VerificationState::Pending.as_str(),
+    }
+}
+
+/// Returns the result for the review card, or an error if the claim can be approved again later.
+async fn approve(http: &Arc<Http>, claim: &Claim, guild_id: GuildId, user_id: UserId, moderator: UserId) -> Result<String, String> {
+    let query = pending(claim);
+    if !matches!(links::coll().find_one(query).await, Ok(Some(_))) {
+        return Ok(format!("The verification of <@{}> is not pending anymore.", user_id));
+    }
+
+    let settings = crate::settings();
+    let profile = match settings.get_hotel(&claim.hotel) {
+        Some(hotel) => crate::habbo_api::profile_by_id(hotel, &claim.unique_id).await.ok(),
+        None => None,
+    };
+    let reason = format!("motto matched the verification code, approved by the moderator {}", moderator);
+    let completed = crate::commands::verify::complete(http, guild_id, user_id, &claim.hotel, &claim.unique_id, profile.as_ref(), &reason);
+
+    let owners = owners(guild_id, user_id, &claim.hotel, &claim.unique_id, &claim.habbo).await;
+    let reason = format!("Habbo has been transferred to the Discord user {} by the moderator {}", user_id, moderator);
+    if !transfer(http, guild_id, &owners, &reason, completed).await {
+        return Err(format!("The verification of <@{}> could not be completed! Please try again later.", user_id));
+    }
+
+    crate::helper::dm(http, user_id, format!(
+        "Hello <@{}> :)\n\nA moderator approved your verification as Habbo `{}`. Congratulations!",
+        user_id,
+        claim.habbo,
+    )).await;
+    Ok(format!("Approved by <@{}>, the Habbo `{}` has been transferred to <@{}>.", moderator, claim.habbo, user_id))
+}
+
+async fn deny(http: &Arc<Http>, claim: &Claim, guild_id: GuildId, user_id: UserId, moderator: UserId) -> String {
+    let query = pending(claim);
+    let reason = format!("claim has been denied by the moderator {}", moderator);
+    if let Err(e) = links::transition(http, query, VerificationState::Expired, &reason, doc! {}).await {
+        warn!("Cannot expire the claim of the Discord user {}: {}", user_id, e);
+    }
+
+    crate::helper::dm(http, user_id, format!(
+        "Hello <@{}> :)\n\nA moderator denied your verification as Habbo `{}`. The Habbo stays linked to another member.",
+        user_id,
+        claim.habbo,
+    )).await;
+    crate::helper::log(http, guild_id, format!(
+        "The claim of <@{}> for the Habbo `{}` has been denied by <@{}>.",
+        user_id,
+        claim.habbo,
+        moderator,
+    )).await;
+    format!("Denied by <@{}>, the Habbo `{}` stays with its owner.", moderator, claim.habbo)
+}
+
+/// Handles the buttons of a review card, see `open`.
+pub async fn component(http: &Arc<Http>, action: &str, interaction: &ComponentInteraction) {
+    let is_mod = interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.administrator() || permissions.manage_roles());
+    if !is_mod {
+        let msg = format!("Hello <@{}> :)\n\nYou are not allowed to review claims!", interaction.user.id);
+        return crate::helper::reply_component(http, msg, interaction).await;
+    }
+
+    // Unknown decisions are ignored, so the claim stays open.
+    let (approved, id) = match action.split_once(':') {
+        Some(("approve", id)) => (true, bson::oid::ObjectId::parse_str(id)),
+        Some(("deny", id)) => (false, bson::oid::ObjectId::parse_str(id)),
+        _ => return crate::helper::acknowledge(http, interaction).await,
+    };
+    let id = match id {
+        Ok(r) => r,
+        Err(_) => return crate::helper::acknowledge(http, interaction).await,
+    };
+
+    // Locking the claim first makes sure it is only reviewed once.
+    let query = doc! {
+        "_id": id,
+        "reviewing": { "$ne": true },
+    };
+    let claim = match coll().find_one_and_update(query, doc! { "$set": { "reviewing": true } }).await {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            let msg = format!("Hello <@{}> :)\n\nThis claim is being reviewed, has already been reviewed or has expired!", interaction.user.id);
+            return crate::helper::reply_component(http, msg, interaction).await;
+        }
+        Err(e) => {
+            error!("{}", e);
+            let msg = format!("Hello <@{}> :)\n\nSomething went wrong! Please try again later!", interaction.user.id);
+            return crate::helper::reply_component(http, msg, interaction).await;
+        }
+    };
+
+    let (guild_id, user_id) = match (claim.guild.parse::<u64>(), claim.user.parse::<u64>()) {
+        (Ok(guild_id), Ok(user_id)) => (GuildId::new(guild_id), UserId::new(user_id)),
+        _ => return crate::helper::acknowledge(http, interaction).await,
+    };
+
+    let moderator = interaction.user.id;
+    let result = match approved {
+        true => approve(http, &claim, guild_id, user_id, moderator).await,
+        false => Ok(deny(http, &claim, guild_id, user_id, moderator).await),
+    };
+
+    // The claim and its buttons are kept, so a moderator can try again.
+    let result = match result {
+        Ok(r) => r,
+        Err(msg) => {
+            if let Err(e) = coll().update_one(doc! { "_id": id }, doc! { "$set": { "reviewing": false } }).await {
+                error!("Cannot unlock the claim {}: {}", id, e);
+            }
+            let msg = format!("Hello <@{}> :)\n\n{}", interaction.user.id, msg);
+            return crate::helper::reply_component(http, msg, interaction).await;
+        }
+    };
+
+    if let Err(e) = coll().delete_one(doc! { "_id": id }).await {
+        error!("Cannot delete the claim {}: {}", id, e);
+    }
+
+    let data = CreateInteractionResponseMessage::new()
+        .content(result)
+        .components(Vec::new());
+    if let Err(e) = interaction.create_response(http, CreateInteractionResponse::UpdateMessage(data)).await {
+        error!("Cannot update the claim: {}", e);
+    }
+}
+
+/// Expires the claims nobody has reviewed in time and tells the claimants.
+pub async fn expire(http: &Arc<Http>) {
+    let cutoff = bson::DateTime::from_millis(bson::DateTime::now().timestamp_millis() - CLAIM_TTL_MILLIS);
+    let claims: Vec<Claim> = match coll().find(doc! { "created_at": { "$lt": cutoff } }).await {
+        Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+        Err(e) => {
+            error!("Cannot load claims: {}", e);
+            return;
+        }
+    };
+
+    for claim in claims {
+        // Claims which are locked are being reviewed right now.
+        let query = doc! {
+            "_id": claim.id,
+            "reviewing": { "$ne": true },
+        };
+        match coll().delete_one(query).await {
+            Ok(r) if r.deleted_count > 0 => {}
+            Ok(_) => continue,
+            Err(e) => {
+                error!("Cannot delete the claim {}: {}", claim.id, e);
+                continue;
+            }
+        }
+
+        if let Err(e) = links::transition(http, pending(&claim), VerificationState::Expired, "claim was not reviewed in time", doc! {}).await {
+            warn!("Cannot expire the claim of the Discord user {}: {}", claim.user, e);
+            continue;
+        }
+        if let Ok(user_id) = claim.user.parse::<u64>() {
+            let user_id = UserId::new(user_id);
+            crate::helper::dm(http, user_id, format!(
+                "Hello <@{}> :)\n\nNo moderator reviewed your verification as Habbo `{}` in time. You can verify it again with `/verify`!",
+                user_id,
+                claim.habbo,
+            )).await;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use mongodb::Collection;
+use serenity::all::{ChannelId, Colour, CreateEmbed, CreateMessage, Http, Timestamp};
+
+use crate::structs::{LinkEvent, LinkEventKind};
+
+pub fn coll() -> Collection<LinkEvent> {
+    crate::mongo::get_coll("link_events")
+}
+
+fn embed(event: &LinkEvent) -> CreateEmbed {
+    let (title, colour) = match event.kind {
+        LinkEventKind::Started => ("Verification started", Colour::BLUE),
+        LinkEventKind::Succeeded => ("Verification succeeded", Colour::DARK_GREEN),
+        LinkEventKind::Failed => ("Verification failed", Colour::ORANGE),
+        LinkEventKind::Reset => ("Link reset", Colour::LIGHT_GREY),
+        LinkEventKind::Transferred => ("Link transferred", Colour::GOLD),
+        LinkEventKind::Revoked => ("Link revoked", Colour::RED),
+        LinkEventKind::Restored => ("Transfer rolled back", Colour::DARK_GOLD),
+    };
+
+    let settings = crate::settings();
+    let hotel = settings
+        .get_hotel(&event.hotel)
+        .map_or(&*event.hotel, |hotel| hotel.get_name());
+    let embed = CreateEmbed::new()
+        .title(title)
+        .colour(colour)
+        .field("Member", format!("<@{}>", event.user), true)
+        .field("Habbo", format!("`{}`", event.habbo), true)
+        .field("Hotel", hotel, true)
+        .field("Reason", &*event.reason, false);
+
+    match Timestamp::from_millis(event.at.timestamp_millis()) {
+        Ok(at) => embed.timestamp(at),
+        Err(_) => embed,
+    }
+}
+
+/// Stores the event in the collection `link_events` and posts it to the audit channel of the guild.
+pub async fn record(http: &Arc<Http>, event: LinkEvent) {
+    if let Err(e) = coll().insert_one(&event).await {
+        error!("Cannot record the event `{}` of the Discord user {}: {}", event.kind.as_str(), event.user, e);
+    }
+
+    let guild_id = match event.guild.parse::<u64>() {
+        Ok(r) => serenity::all::GuildId::new(r),
+        Err(_) => return,
+    };
+    let channel_id = crate::guilds::get(guild_id).await.audit_channel_id;
+    if channel_id < crate::LOWEST_ID {
+        return;
+    }
+
+    let message = CreateMessage::new().embed(embed(&event));
+    if let Err(e) = ChannelId::new(channel_id).send_message(http, message).await {
+        error!("Cannot post to the audit channel: {}", e);
+    }
+}
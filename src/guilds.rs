@@ -0,0 +1,138 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use bson::Document;
+use mongodb::Collection;
+use serenity::all::GuildId;
+
+use crate::structs::GuildConfig;
+
+fn coll() -> Collection<GuildConfig> {
+    crate::mongo::get_coll("guild_configs")
+}
+
+/// All changes go through `save`, so the cached configurations are always up to date.
+fn cache() -> &'static RwLock<HashMap<GuildId, GuildConfig>> {
+    use std::sync::OnceLock;
+    static VAL: OnceLock<RwLock<HashMap<GuildId, GuildConfig>>> = OnceLock::new();
+    VAL.get_or_init(Default::default)
+}
+
+/// Returns the configuration of the guild, or the defaults if it has not been configured yet.
+pub async fn get(guild_id: GuildId) -> GuildConfig {
+    if let Some(config) = cache().read().unwrap().get(&guild_id) {
+        return config.clone();
+    }
+
+    let query = doc! {
+        "guild_id": guild_id.to_string(),
+    };
+    let config = match coll().find_one(query).await {
+        Ok(Some(r)) => r,
+        Ok(None) => GuildConfig {
+            guild_id: guild_id.to_string(),
+            ..Default::default()
+        },
+        Err(e) => {
+            // Not cached, so the next call tries again.
+            error!("Cannot load the configuration of the guild {}: {}", guild_id, e);
+            return GuildConfig {
+                guild_id: guild_id.to_string(),
+                ..Default::default()
+            };
+        }
+    };
+
+    cache().write().unwrap().insert(guild_id, config.clone());
+    config
+}
+
+pub async fn save(config: GuildConfig) -> bool {
+    let guild_id = match config.guild_id.parse::<u64>() {
+        Ok(r) if r != 0 => GuildId::new(r),
+        _ => return false,
+    };
+
+    let query = doc! {
+        "guild_id": &config.guild_id,
+    };
+    let options = mongodb::options::ReplaceOptions::builder().upsert(true).build();
+    if let Err(e) = coll().replace_one(query, &config).with_options(options).await {
+        error!("{}", e);
+        return false;
+    }
+
+    cache().write().unwrap().insert(guild_id, config);
+    true
+}
+
+/// Whether any guild has a nickname template, see `nicknames::revert`.
+pub async fn uses_nicknames() -> bool {
+    let query = doc! {
+        "nickname_template": { "$exists": true, "$ne": "" },
+    };
+    match coll().find_one(query).await {
+        Ok(r) => r.is_some(),
+        Err(e) => {
+            error!("{}", e);
+            false
+        }
+    }
+}
+
+/// Moves the guild of older versions from `settings.json` into `guild_configs`
+/// and assigns it all links which do not belong to a guild yet.
+/// The section `guild` is removed from `settings.json` once everything has been migrated.
+pub async fn migrate() {
+    let settings = crate::settings();
+    let guild = match settings.get_guild() {
+        Some(r) => r,
+        None => return,
+    };
+    let guild_id = guild.get_id().to_string();
+
+    let query = doc! {
+        "guild_id": &guild_id,
+    };
+    let mut migrated = match coll().count_documents(query).await {
+        Ok(0) => {
+            let saved = save(guild.to_config()).await;
+            if saved {
+                info!("Migrated the guild {} from `{}`", guild_id, crate::config::path());
+            }
+            saved
+        }
+        Ok(_) => true,
+        Err(e) => {
+            error!("Cannot migrate the guild {}: {}", guild_id, e);
+            false
+        }
+    };
+
+    for name in ["verified_users", "pending_verifications"] {
+        let coll = crate::mongo::get_coll::<Document>(name);
+        let query = doc! {
+            "guild": { "$exists": false },
+        };
+        let update = doc! {
+            "$set": {
+                "guild": &guild_id,
+            },
+        };
+
+        match coll.update_many(query, update).await {
+            Ok(r) if r.modified_count > 0 => info!("Migrated {} documents of `{}` to the guild {}", r.modified_count, name, guild_id),
+            Ok(_) => {}
+            Err(e) => {
+                error!("Cannot migrate `{}`: {}", name, e);
+                migrated = false;
+            }
+        }
+    }
+
+    if !migrated {
+        return;
+    }
+    if let Err(e) = crate::config::update(|settings| settings.remove_guild()) {
+        error!("Cannot remove the migrated guild from `{}`: {}", crate::config::path(), e);
+    }
+}
@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use reqwest::{StatusCode, Url};
+
+use crate::structs::{Hotel, Profile, VerifiedUser};
+
+const USERS_PATH: &str = "/api/public/users";
+
+/// Longer waits requested by the hotel are not worth blocking a verification or a job for.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+pub enum HabboApiError {
+    NotFound,
+    Private,
+    /// The hotel asked us to wait, optionally for the given duration.
+    RateLimited(Option<Duration>),
+    HotelDown(String),
+    /// The hotel refused the request, sending it again will not help.
+    Rejected(String),
+    Malformed(String),
+}
+
+impl HabboApiError {
+    /// Whether the same request can succeed later.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::RateLimited(_) | Self::HotelDown(_) | Self::Malformed(_))
+    }
+
+    /// Explains the error to the user who requested the Habbo.
+    pub fn user_message(&self, habbo: &str) -> String {
+        match self {
+            Self::NotFound => format!("The Habbo \"{}\" does not exist!", habbo),
+            Self::Private => format!("The profile of the Habbo \"{}\" has been set to private!", habbo),
+            Self::RateLimited(_) => "Habbo Hotel:Origins is receiving too many requests! Please try again in a minute!".to_string(),
+            Self::HotelDown(_) => "The Habbo Hotel:Origins request has failed! Please try again later!".to_string(),
+            Self::Rejected(_) => "Habbo Hotel:Origins rejected the request! Please contact a moderator!".to_string(),
+            Self::Malformed(_) => "Habbo Hotel:Origins sent an unexpected response! Please try again later!".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for HabboApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "not found"),
+            Self::Private => write!(f, "profile is private"),
+            Self::RateLimited(Some(d)) => write!(f, "rate limited for {} seconds", d.as_secs()),
+            Self::RateLimited(None) => write!(f, "rate limited"),
+            Self::HotelDown(e) => write!(f, "hotel unavailable: {}", e),
+            Self::Rejected(e) => write!(f, "request rejected: {}", e),
+            Self::Malformed(e) => write!(f, "malformed response: {}", e),
+        }
+    }
+}
+
+fn client() -> &'static reqwest::Client {
+    use std::sync::OnceLock;
+    static VAL: OnceLock<reqwest::Client> = OnceLock::new();
+    VAL.get_or_init(|| {
+        // Changes of the timeout require a restart.
+        let timeout = crate::settings().get_habbo_api().get_timeout();
+        let builder = reqwest::Client::builder()
+            .user_agent(concat!("originsbot/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(timeout))
+            .timeout(Duration::from_secs(timeout));
+        match builder.build() {
+            Ok(r) => r,
+            Err(e) => panic!("{}", e),
+        }
+    })
+}
+
+async fn request(url: &Url) -> Result<Profile, HabboApiError> {
+    let res = client()
+        .get(url.clone())
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| HabboApiError::HotelDown(e.to_string()))?;
+
+    let status = res.status();
+    match status {
+        StatusCode::NOT_FOUND => return Err(HabboApiError::NotFound),
+        StatusCode::FORBIDDEN => return Err(HabboApiError::Private),
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after = res
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Err(HabboApiError::RateLimited(retry_after));
+        }
+        s if s.is_server_error() => return Err(HabboApiError::HotelDown(s.to_string())),
+        s if s.is_client_error() => return Err(HabboApiError::Rejected(s.to_string())),
+        s if !s.is_success() => return Err(HabboApiError::Malformed(s.to_string())),
+        _ => {}
+    }
+
+    let text = res.text().await.map_err(|e| HabboApiError::HotelDown(e.to_string()))?;
+    let value = serde_json::from_str::<serde_json::Value>(&text)
+        .map_err(|e| HabboApiError::Malformed(e.to_string()))?;
+
+    // Some errors are sent with the status code 200.
+    if let Some(e) = value.get("error") {
+        let e = e.as_str().unwrap_or_default();
+        if e.contains("private") {
+            return Err(HabboApiError::Private);
+        }
+        return Err(HabboApiError::NotFound);
+    }
+
+    let profile = serde_json::from_value::<Profile>(value)
+        .map_err(|e| HabboApiError::Malformed(e.to_string()))?;
+    if profile.profile_visible == Some(false) {
+        return Err(HabboApiError::Private);
+    }
+    if profile.unique_id.is_empty() {
+        return Err(HabboApiError::Malformed("the unique id is missing".to_string()));
+    }
+    Ok(profile)
+}
+
+/// Sends the request and retries it as configured, as long as the error is transient.
+/// Gives up if the hotel asks to wait longer than `MAX_RETRY_AFTER`.
+async fn get(url: Url) -> Result<Profile, HabboApiError> {
+    let retries = crate::settings().get_habbo_api().get_retries();
+
+    let mut attempt = 0;
+    loop {
+        let e = match request(&url).await {
+            Ok(r) => return Ok(r),
+            Err(e) => e,
+        };
+        if !e.is_transient() || attempt >= retries {
+            return Err(e);
+        }
+        attempt += 1;
+
+        let wait = match &e {
+            HabboApiError::RateLimited(Some(d)) if *d > MAX_RETRY_AFTER => return Err(e),
+            HabboApiError::RateLimited(Some(d)) => *d,
+            _ => Duration::from_secs(attempt as u64),
+        };
+        warn!("Habbo Hotel:Origins request failed ({}), retry {} of {} in {} seconds", e, attempt, retries, wait.as_secs());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+fn users_url(hotel: &Hotel) -> Result<Url, HabboApiError> {
+    let url = format!("{}{}", hotel.get_url().trim_end_matches('/'), USERS_PATH);
+    Url::parse(&url).map_err(|e| HabboApiError::Malformed(e.to_string()))
+}
+
+pub async fn profile_by_name(hotel: &Hotel, name: &str) -> Result<Profile, HabboApiError> {
+    let mut url = users_url(hotel)?;
+    url.query_pairs_mut().append_pair("name", name);
+    get(url).await
+}
+
+/// Unlike the name, the unique id of a Habbo never changes.
+pub async fn profile_by_id(hotel: &Hotel, unique_id: &str) -> Result<Profile, HabboApiError> {
+    let mut url = users_url(hotel)?;
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.push(unique_id);
+    }
+    get(url).await
+}
+
+/// Looks up the Habbo of the link, by its unique id or, for links of older versions, by its name.
+pub async fn profile_of(link: &VerifiedUser) -> Result<Profile, HabboApiError> {
+    let settings = crate::settings();
+    let hotel = settings
+        .get_hotel(&link.hotel)
+        .ok_or_else(|| HabboApiError::Rejected(format!("the hotel `{}` is not available", link.hotel)))?;
+    match link.unique_id.is_empty() {
+        true => profile_by_name(hotel, &link.habbo).await,
+        false => profile_by_id(hotel, &link.unique_id).await,
+    }
+}
@@ -1,45 +1,122 @@
 use std::sync::Arc;
 
-use serenity::all::{CommandInteraction, Http};
-
-#[deny(clippy::mut_from_ref)]
-pub unsafe fn ref_to_refmut<T>(val: &T) -> &mut T {
-    (val as *const T as *mut T).as_mut().unwrap_unchecked()
-}
-
-pub async fn reqwest<T, R>(url: &str, cb: T) -> (bool, Option<R>)
-where
-    T: Fn(reqwest::Response) -> R,
-{
-    let client = reqwest::Client::new();
-    let req = client
-        .get(url)
-        .header(reqwest::header::CONTENT_TYPE, "application/json")
-        .header(
-            reqwest::header::USER_AGENT,
-            "Mozilla/1.22 (compatible; MSIE 5.01; PalmOS 3.0) EudoraWeb 2",
-        );
-
-    let res = match req.send().await {
-        Ok(r) => r,
-        Err(e) => {
-            error!("{}", e);
-            return (false, None);
-        }
-    };
+use serenity::all::{CommandInteraction, ComponentInteraction, CreateActionRow, GuildId, Http, UserId};
 
-    (true, Some(cb(res)))
+/// Normalizes a Habbo name before it is written or looked up. The casing is kept,
+/// so the name can be displayed as in the hotel. Use `mongo::habbo_collation` to compare names.
+pub fn normalize_habbo(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string()
 }
 
-pub async fn edit_reply(http: &Arc<Http>, msg: String, interaction: &CommandInteraction) {
-    use serenity::all::EditInteractionResponse;
+pub fn option_str<'a>(interaction: &'a CommandInteraction, name: &str) -> Option<&'a str> {
+    interaction
+        .data
+        .options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.value.as_str())
+}
+
+/// The hotel selected with the option `hotel`, or the default hotel if the option is missing.
+/// Returns `None` if the selected hotel does not exist (anymore).
+pub fn hotel(interaction: &CommandInteraction) -> Option<crate::structs::Hotel> {
+    let settings = crate::settings();
+    match option_str(interaction, "hotel") {
+        Some(id) => settings.get_hotel(id).cloned(),
+        None => Some(settings.get_default_hotel().clone()),
+    }
+}
+
+pub fn hotel_option() -> serenity::all::CreateCommandOption {
+    use serenity::all::{CommandOptionType, CreateCommandOption};
 
-    if let Err(e) = interaction.edit_response(http, EditInteractionResponse::new().content(msg)).await {
+    let mut option = CreateCommandOption::new(
+        CommandOptionType::String,
+        "hotel",
+        "The hotel of the Habbo",
+    )
+    .required(false);
+    for hotel in crate::settings().get_hotels() {
+        option = option.add_string_choice(hotel.get_name(), hotel.get_id());
+    }
+    option
+}
+
+/// Formats a date as Discord timestamp, which is shown in the local time of the reader.
+pub fn timestamp(date: bson::DateTime) -> String {
+    format!("<t:{}:f>", date.timestamp_millis() / 1000)
+}
+
+pub async fn edit_reply(http: &Arc<Http>, msg: String, interaction: &CommandInteraction) {
+    if let Err(e) = edit_token(http, &interaction.token, msg, Vec::new()).await {
         error!("Cannot edit respond: {}", e);
     }
 }
 
-pub async fn reply(http: &Arc<Http>, msg: String, interaction: &CommandInteraction) {
+/// Edits the original response of an interaction by its token.
+/// Unlike `edit_reply`, this also works after a restart of the bot.
+pub async fn edit_token(
+    http: &Arc<Http>,
+    token: &str,
+    msg: String,
+    components: Vec<CreateActionRow>,
+) -> serenity::Result<()> {
+    use serenity::{all::EditInteractionResponse, builder::Builder};
+
+    EditInteractionResponse::new()
+        .content(msg)
+        .components(components)
+        .execute(http, token)
+        .await
+        .map(|_| ())
+}
+
+/// Posts a message to the channel for moderators of the guild, if it has been set.
+pub async fn log(http: &Arc<Http>, guild_id: GuildId, msg: String) {
+    use serenity::all::{ChannelId, CreateMessage};
+
+    let channel_id = crate::guilds::get(guild_id).await.log_channel_id;
+    if channel_id < crate::LOWEST_ID {
+        return;
+    }
+
+    if let Err(e) = ChannelId::new(channel_id).send_message(http, CreateMessage::new().content(msg)).await {
+        error!("Cannot post to the log channel: {}", e);
+    }
+}
+
+/// Sends a direct message to the user. Fails silently if the user does not accept direct messages.
+pub async fn dm(http: &Arc<Http>, user_id: UserId, msg: String) {
+    use serenity::all::CreateMessage;
+
+    if let Err(e) = user_id.direct_message(http, CreateMessage::new().content(msg)).await {
+        warn!("Cannot send a direct message to the user {}: {}", user_id, e);
+    }
+}
+
+/// Tells the user the result of an interaction. Interaction tokens are only valid for 15 minutes,
+/// so a direct message is sent if the original response can no longer be edited.
+pub async fn notify(http: &Arc<Http>, user_id: UserId, token: &str, msg: String) {
+    if edit_token(http, token, msg.clone(), Vec::new()).await.is_ok() {
+        return;
+    }
+
+    dm(http, user_id, msg).await;
+}
+
+pub async fn acknowledge(http: &Arc<Http>, interaction: &ComponentInteraction) {
+    use serenity::all::CreateInteractionResponse;
+
+    if let Err(e) = interaction.create_response(http, CreateInteractionResponse::Acknowledge).await {
+        error!("Cannot acknowledge component: {}", e);
+    }
+}
+
+pub async fn reply_component(http: &Arc<Http>, msg: String, interaction: &ComponentInteraction) {
     use serenity::all::{CreateInteractionResponse, CreateInteractionResponseMessage};
 
     let data = CreateInteractionResponseMessage::new()
@@ -50,3 +127,36 @@ pub async fn reply(http: &Arc<Http>, msg: String, interaction: &CommandInteracti
         error!("Cannot create respond: {}", e);
     }
 }
+
+pub async fn reply(http: &Arc<Http>, msg: String, interaction: &CommandInteraction) {
+    use serenity::all::{CreateInteractionResponse, CreateInteractionResponseMessage};
+
+    let ephemeral = match interaction.guild_id {
+        Some(guild_id) => crate::guilds::get(guild_id).await.ephemeral_replies,
+        None => true,
+    };
+    let data = CreateInteractionResponseMessage::new()
+        .content(msg)
+        .ephemeral(ephemeral);
+    let builder = CreateInteractionResponse::Message(data);
+    if let Err(e) = interaction.create_response(http, builder).await {
+        error!("Cannot create respond: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_habbo_keeps_the_casing() {
+        assert_eq!(normalize_habbo("MiXeD.Case"), "MiXeD.Case");
+        assert_eq!(normalize_habbo("  Foo-Bar \n"), "Foo-Bar");
+    }
+
+    #[test]
+    fn normalize_habbo_removes_control_characters() {
+        assert_eq!(normalize_habbo("Fo\u{0}o\t"), "Foo");
+        assert_eq!(normalize_habbo("\u{7f}"), "");
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{Http, UserId},
+    futures::TryStreamExt,
+};
+
+use crate::structs::{VerificationState, VerifiedUser};
+
+/// Returns when the Habbo has been created. Links verified before the age roles existed
+/// do not know it yet, so it is retrieved once and stored.
+async fn member_since(user: &VerifiedUser) -> Option<bson::DateTime> {
+    if user.member_since.is_some() {
+        return user.member_since;
+    }
+
+    let member_since = crate::habbo_api::profile_of(user).await.ok()?.member_since()?;
+
+    let query = crate::links::filter(user);
+    let update = doc! {
+        "$set": {
+            "member_since": member_since,
+        },
+    };
+    if let Err(e) = crate::links::coll().update_one(query, update).await {
+        error!("{}", e);
+    }
+    Some(member_since)
+}
+
+/// Promotes verified members to the age roles they reached since the last run.
+async fn promote(http: &Arc<Http>) {
+    // Age roles are granted for the primary Habbo only.
+    let query = doc! {
+        "state": VerificationState::Verified.as_str(),
+        "primary": true,
+    };
+    let mut users = match crate::links::coll().find(query).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load links: {}", e);
+            return;
+        }
+    };
+
+    while let Ok(Some(user)) = users.try_next().await {
+        let (user_id, guild_id) = match (user.id.parse::<u64>(), user.guild_id()) {
+            (Ok(user_id), Some(guild_id)) => (UserId::new(user_id), guild_id),
+            _ => continue,
+        };
+        // Do not look up Habbos for guilds without age roles.
+        let config = crate::guilds::get(guild_id).await;
+        if config.age_roles.is_empty() || !config.age_roles_enabled {
+            continue;
+        }
+        if let Some(member_since) = member_since(&user).await {
+            crate::roles::sync_age(http, guild_id, user_id, member_since).await;
+        }
+    }
+}
+
+pub async fn run(http: Arc<Http>) {
+    let secs = crate::settings().get_jobs().get_age_interval();
+    super::every(secs, || promote(&http)).await;
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use serenity::all::{GuildId, Http, UserId};
+
+/// Returns the guilds which have badge roles and have not disabled them.
+async fn guilds() -> Vec<String> {
+    let guilds = match crate::roles::badge_coll().distinct("guild", doc! {}).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load the badge roles: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut enabled = Vec::new();
+    for guild in guilds.iter().filter_map(|guild| guild.as_str()) {
+        let guild_id = match guild.parse::<u64>() {
+            Ok(r) if r != 0 => GuildId::new(r),
+            _ => continue,
+        };
+        if crate::guilds::get(guild_id).await.badge_roles_enabled {
+            enabled.push(guild.to_string());
+        }
+    }
+    enabled
+}
+
+/// Keeps the badge roles of all verified members in sync with the badges they wear.
+pub async fn run(http: Arc<Http>) {
+    let secs = crate::settings().get_jobs().get_badges_interval();
+    super::every(secs, || {
+        let http = Arc::clone(&http);
+        async move {
+            // Profiles are only looked up where they are needed.
+            let guilds = guilds().await;
+            if guilds.is_empty() {
+                return;
+            }
+
+            // Badge roles are granted for the primary Habbo only.
+            let query = doc! {
+                "guild": { "$in": guilds },
+                "primary": true,
+            };
+            super::for_each_profile(query, |user, profile| {
+                let http = Arc::clone(&http);
+                async move {
+                    if let (Ok(user_id), Some(guild_id)) = (user.id.parse::<u64>(), user.guild_id()) {
+                        crate::roles::sync_badges(&http, guild_id, UserId::new(user_id), &profile).await;
+                    }
+                }
+            })
+            .await;
+        }
+    })
+    .await;
+}
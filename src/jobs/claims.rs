@@ -0,0 +1,10 @@
+use std::sync::Arc;
+
+use serenity::all::Http;
+
+/// How often claims are checked. They expire after a week, so an hour late does not matter.
+const INTERVAL_SECS: u64 = 60 * 60;
+
+pub async fn run(http: Arc<Http>) {
+    super::every(INTERVAL_SECS, || crate::conflicts::expire(&http)).await;
+}
@@ -0,0 +1,71 @@
+use std::{future::Future, sync::Arc};
+
+use bson::Document;
+use serenity::{all::Http, futures::TryStreamExt};
+use tokio::time::Duration;
+
+use crate::structs::{Profile, VerificationState, VerifiedUser};
+
+pub mod age;
+pub mod badges;
+pub mod claims;
+pub mod presence;
+pub mod revalidate;
+pub mod rename;
+
+/// Starts all background jobs. `ready` is called again after every reconnect,
+/// so the jobs are only started once.
+pub fn spawn(http: &Arc<Http>) {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static SPAWNED: AtomicBool = AtomicBool::new(false);
+    if SPAWNED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(rename::run(Arc::clone(http)));
+    tokio::spawn(badges::run(Arc::clone(http)));
+    tokio::spawn(age::run(Arc::clone(http)));
+    tokio::spawn(presence::run(Arc::clone(http)));
+    tokio::spawn(revalidate::run(Arc::clone(http)));
+    tokio::spawn(claims::run(Arc::clone(http)));
+}
+
+/// Calls `f` every `secs` seconds, starting immediately.
+/// If `f` takes longer than `secs` seconds, the next call is delayed accordingly.
+async fn every<F, Fut>(secs: u64, mut f: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut interval = tokio::time::interval(Duration::from_secs(secs.max(60)));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        f().await;
+    }
+}
+
+/// Looks up the Habbo of every verified link matched by `query` and calls `f` with the link and its current profile.
+/// Links whose Habbo cannot be retrieved are skipped.
+async fn for_each_profile<F, Fut>(mut query: Document, mut f: F)
+where
+    F: FnMut(VerifiedUser, Profile) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    query.insert("state", VerificationState::Verified.as_str());
+    let mut users = match crate::links::coll().find(query).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load links: {}", e);
+            return;
+        }
+    };
+
+    while let Ok(Some(user)) = users.try_next().await {
+        match crate::habbo_api::profile_of(&user).await {
+            Ok(profile) => f(user, profile).await,
+            Err(e) => warn!("Cannot retrieve the Habbo `{}` of the Discord user {}: {}", user.habbo, user.id, e),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{Http, UserId},
+    futures::TryStreamExt,
+};
+use tokio::time::Duration;
+
+use crate::structs::VerificationState;
+
+/// Checks all verified Habbos in batches, so no more than `presence_budget` requests per minute
+/// are sent to the hotel, and updates the presence role of their members.
+async fn check(http: &Arc<Http>) {
+    let query = doc! {
+        "state": VerificationState::Verified.as_str(),
+        "unique_id": { "$type": "string" },
+        "primary": true,
+    };
+    let mut users = match crate::links::coll().find(query).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load links: {}", e);
+            return;
+        }
+    };
+
+    let budget = crate::settings().get_jobs().get_presence_budget().max(1);
+    let mut pace = tokio::time::interval(Duration::from_secs(60) / budget);
+
+    while let Ok(Some(user)) = users.try_next().await {
+        let settings = crate::settings();
+        let hotel = settings.get_hotel(&user.hotel);
+        let (user_id, guild_id, hotel) = match (user.id.parse::<u64>(), user.guild_id(), hotel) {
+            (Ok(user_id), Some(guild_id), Some(hotel)) => (UserId::new(user_id), guild_id, hotel),
+            _ => continue,
+        };
+        // Do not spend the budget on guilds without a presence role.
+        let config = crate::guilds::get(guild_id).await;
+        if config.presence_role_id < crate::LOWEST_ID || !config.presence_role_enabled {
+            continue;
+        }
+
+        pace.tick().await;
+        match crate::habbo_api::profile_by_id(hotel, &user.unique_id).await {
+            Ok(profile) => crate::roles::sync_presence(http, guild_id, user_id, profile.online).await,
+            // Do not keep members in the hotel whose Habbo cannot be checked.
+            Err(e) if !e.is_transient() => crate::roles::sync_presence(http, guild_id, user_id, false).await,
+            Err(_) => {}
+        }
+    }
+}
+
+pub async fn run(http: Arc<Http>) {
+    let secs = crate::settings().get_jobs().get_presence_interval();
+    super::every(secs, || check(&http)).await;
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use serenity::all::{Http, UserId};
+
+use crate::structs::{Profile, VerifiedUser};
+
+/// Refreshes the cached display name of a verified Habbo and the nickname of the member.
+/// Links of older versions, which only know the name, get their unique id.
+async fn refresh(http: &Arc<Http>, user: VerifiedUser, profile: Profile) {
+    let name = crate::helper::normalize_habbo(&profile.name);
+    if name.is_empty() || (name == *user.habbo && *profile.unique_id == *user.unique_id) {
+        return;
+    }
+
+    let query = crate::links::filter(&user);
+    let update = doc! {
+        "$set": {
+            "habbo": &name,
+            "unique_id": &*profile.unique_id,
+        },
+    };
+    if let Err(e) = crate::links::coll().update_one(query, update).await {
+        error!("{}", e);
+        return;
+    }
+
+    if name != *user.habbo {
+        info!(
+            "Habbo `{}` ({}) of the Discord user {} has been renamed to `{}`",
+            user.habbo, profile.unique_id, user.id, name
+        );
+
+        if let (Ok(user_id), Some(guild_id)) = (user.id.parse::<u64>(), user.guild_id()) {
+            crate::nicknames::sync(http, guild_id, UserId::new(user_id)).await;
+        }
+    }
+}
+
+pub async fn run(http: Arc<Http>) {
+    let secs = crate::settings().get_jobs().get_rename_interval();
+    super::every(secs, || {
+        super::for_each_profile(doc! {}, |user, profile| {
+            let http = Arc::clone(&http);
+            async move { refresh(&http, user, profile).await }
+        })
+    })
+    .await;
+}
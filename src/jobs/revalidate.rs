@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use serenity::{
+    all::{GuildId, Http, UserId},
+    futures::TryStreamExt,
+};
+
+use crate::{
+    habbo_api::HabboApiError,
+    structs::{VerificationState, VerifiedUser},
+};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Members get at least a day to fix their Habbo, even if a shorter grace period is configured.
+fn grace_secs() -> u64 {
+    crate::settings().get_jobs().get_revalidate_grace().max(DAY_SECS)
+}
+
+/// Returns why the link is not valid anymore, or `None` if it is valid.
+/// Returns `Err` if the Habbo cannot be checked right now.
+async fn problem(user: &VerifiedUser) -> Result<Option<String>, ()> {
+    match crate::habbo_api::profile_of(user).await {
+        Ok(_) => Ok(None),
+        Err(HabboApiError::NotFound) => Ok(Some("the Habbo does not exist anymore".to_string())),
+        Err(HabboApiError::Private) => Ok(Some("the profile of the Habbo has been set to private".to_string())),
+        Err(_) => Err(()),
+    }
+}
+
+async fn unflag(user: &VerifiedUser) {
+    let query = crate::links::filter(user);
+    let update = doc! {
+        "$unset": {
+            "flagged_at": "",
+            "flag_reason": "",
+        },
+    };
+    if let Err(e) = crate::links::coll().update_one(query, update).await {
+        error!("{}", e);
+    }
+}
+
+async fn flag(http: &Arc<Http>, user: &VerifiedUser, guild_id: GuildId, user_id: UserId, reason: &str) {
+    let query = crate::links::filter(user);
+    let update = doc! {
+        "$set": {
+            "flagged_at": bson::DateTime::now(),
+            "flag_reason": reason,
+        },
+    };
+    if let Err(e) = crate::links::coll().update_one(query, update).await {
+        error!("{}", e);
+        return;
+    }
+
+    let grace_days = grace_secs() / DAY_SECS;
+    crate::helper::dm(http, user_id, format!(
+        "Hello <@{}> :)\n\nYour verification as Habbo `{}` could not be confirmed: {}. Please fix it within {} day(s), otherwise your verification will be removed!",
+        user_id,
+        user.habbo,
+        reason,
+        grace_days,
+    )).await;
+    crate::helper::log(http, guild_id, format!(
+        "The verification of <@{}> as Habbo `{}` has been flagged: {}.",
+        user_id,
+        user.habbo,
+        reason,
+    )).await;
+}
+
+async fn revoke(http: &Arc<Http>, user: &VerifiedUser, guild_id: GuildId, user_id: UserId, reason: &str) {
+    let query = crate::links::filter(user);
+    let set = doc! {
+        "flagged_at": null,
+        "flag_reason": null,
+    };
+    if let Err(e) = crate::links::transition(http, query, VerificationState::Revoked, reason, set).await {
+        error!("{}", e);
+        return;
+    }
+
+    crate::accounts::removed(http, guild_id, user_id).await;
+
+    crate::helper::dm(http, user_id, format!(
+        "Hello <@{}> :)\n\nYour verification as Habbo `{}` has been removed: {}. Use the command `/verify` to verify yourself again!",
+        user_id,
+        user.habbo,
+        reason,
+    )).await;
+    crate::helper::log(http, guild_id, format!(
+        "The verification of <@{}> as Habbo `{}` has been revoked: {}.",
+        user_id,
+        user.habbo,
+        reason,
+    )).await;
+}
+
+/// Checks whether the Habbos of all verified links still exist. Broken links are flagged first
+/// and revoked if they are still broken after the grace period.
+async fn revalidate(http: &Arc<Http>) {
+    let query = doc! {
+        "state": VerificationState::Verified.as_str(),
+    };
+    let mut users = match crate::links::coll().find(query).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Cannot load links: {}", e);
+            return;
+        }
+    };
+
+    let grace_millis = grace_secs() as i64 * 1000;
+    while let Ok(Some(user)) = users.try_next().await {
+        let (user_id, guild_id) = match (user.id.parse::<u64>(), user.guild_id()) {
+            (Ok(user_id), Some(guild_id)) => (UserId::new(user_id), guild_id),
+            _ => continue,
+        };
+
+        match (problem(&user).await, user.flagged_at) {
+            (Err(_), _) => {}
+            (Ok(None), Some(_)) => unflag(&user).await,
+            (Ok(None), None) => {}
+            (Ok(Some(reason)), None) => flag(http, &user, guild_id, user_id, &reason).await,
+            (Ok(Some(reason)), Some(flagged_at)) => {
+                let flagged_millis = bson::DateTime::now().timestamp_millis() - flagged_at.timestamp_millis();
+                if flagged_millis >= grace_millis {
+                    revoke(http, &user, guild_id, user_id, &reason).await;
+                }
+            }
+        }
+    }
+}
+
+pub async fn run(http: Arc<Http>) {
+    let secs = crate::settings().get_jobs().get_revalidate_interval();
+    super::every(secs, || revalidate(&http)).await;
+}
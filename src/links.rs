@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use bson::Document;
+use mongodb::Collection;
+use serenity::{all::Http, futures::TryStreamExt};
+
+use crate::structs::{LinkEvent, LinkEventKind, Transition, VerificationState, VerifiedUser};
+
+pub enum TransitionError {
+    NotFound,
+    Illegal(VerificationState, VerificationState),
+    /// The link has been changed by someone else in the meantime.
+    Conflict,
+    Database(mongodb::error::Error),
+}
+
+impl std::fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "link not found"),
+            Self::Illegal(from, to) => write!(f, "illegal transition from `{}` to `{}`", from, to),
+            Self::Conflict => write!(f, "link has been changed concurrently"),
+            Self::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<mongodb::error::Error> for TransitionError {
+    fn from(e: mongodb::error::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+pub fn coll() -> Collection<VerifiedUser> {
+    crate::mongo::get_coll("verified_users")
+}
+
+fn new_transition(from: Option<VerificationState>, to: VerificationState, reason: &str) -> Transition {
+    Transition {
+        from,
+        to,
+        at: bson::DateTime::now(),
+        reason: reason.into(),
+    }
+}
+
+fn transition_doc(from: Option<VerificationState>, to: VerificationState, reason: &str) -> bson::Bson {
+    bson::to_bson(&new_transition(from, to, reason)).unwrap_or_default()
+}
+
+/// Matches exactly the given link. Links of older versions are only known by name.
+pub fn filter(link: &VerifiedUser) -> Document {
+    let mut query = doc! {
+        "id": &link.id,
+        "guild": &link.guild,
+        "hotel": &*link.hotel,
+    };
+    match link.unique_id.is_empty() {
+        true => query.insert("habbo", &*link.habbo),
+        false => query.insert("unique_id", &*link.unique_id),
+    };
+    query
+}
+
+/// Creates a new link in the state `pending`.
+pub async fn create(
+    http: &Arc<Http>,
+    id: &str,
+    guild: &str,
+    hotel: &str,
+    habbo: &str,
+    unique_id: &str,
+    reason: &str,
+) -> Result<(), TransitionError> {
+    let user = VerifiedUser {
+        id: id.to_string(),
+        guild: guild.to_string(),
+        habbo: crate::helper::normalize_habbo(habbo).into(),
+        unique_id: unique_id.into(),
+        hotel: hotel.into(),
+        member_since: None,
+        flagged_at: None,
+        flag_reason: None,
+        state: VerificationState::Pending,
+        transitions: vec![new_transition(None, VerificationState::Pending, reason)],
+        primary: false,
+    };
+    coll().insert_one(&user).await?;
+
+    crate::events::record(http, LinkEvent::new(&user, LinkEventKind::Started, reason)).await;
+    Ok(())
+}
+
+/// Moves the link matched by `filter` into the state `to` and records the transition.
+/// `set` contains additional fields to update together with the state.
+///
+/// The update only succeeds if the link is still in the state it was read in,
+/// so two concurrent transitions can never both be applied.
+/// Every applied transition is recorded as an event, see `events::record`.
+pub async fn transition(
+    http: &Arc<Http>,
+    filter: Document,
+    to: VerificationState,
+    reason: &str,
+    set: Document,
+) -> Result<(), TransitionError> {
+    let coll = coll();
+    // Filters by name have to match the Habbo regardless of its casing.
+    let mut user = coll
+        .find_one(filter.clone())
+        .collation(crate::mongo::habbo_collation())
+        .await?
+        .ok_or(TransitionError::NotFound)?;
+    if !user.state.can_transition_to(to) {
+        return Err(TransitionError::Illegal(user.state, to));
+    }
+
+    let mut query = filter;
+    query.insert("state", user.state.as_str());
+
+    // The event describes the Habbo the link points to after the transition.
+    for (key, field) in [("habbo", &mut user.habbo), ("unique_id", &mut user.unique_id), ("hotel", &mut user.hotel)] {
+        if let Ok(val) = set.get_str(key) {
+            *field = val.into();
+        }
+    }
+
+    let mut fields = set;
+    fields.insert("state", to.as_str());
+    let update = doc! {
+        "$set": fields,
+        "$push": {
+            "transitions": transition_doc(Some(user.state), to, reason),
+        },
+    };
+
+    let result = coll.update_one(query, update).collation(crate::mongo::habbo_collation()).await?;
+    if result.matched_count == 0 {
+        return Err(TransitionError::Conflict);
+    }
+
+    crate::events::record(http, LinkEvent::new(&user, LinkEventKind::from_transition(user.state, to), reason)).await;
+    Ok(())
+}
+
+/// Deletes all links matched by `filter` and records a `reset` event for each of them.
+/// Returns how many links have been deleted.
+pub async fn reset(http: &Arc<Http>, filter: Document, reason: &str) -> Result<u64, mongodb::error::Error> {
+    let coll = coll();
+    let users: Vec<VerifiedUser> = coll.find(filter.clone()).await?.try_collect().await?;
+    let result = coll.delete_many(filter).await?;
+
+    for user in &users {
+        crate::events::record(http, LinkEvent::new(user, LinkEventKind::Reset, reason)).await;
+    }
+    Ok(result.deleted_count)
+}
+
+/// Converts documents of older versions, which only know the `verified` flag, into the state machine.
+pub async fn migrate() {
+    let coll = crate::mongo::get_coll::<Document>("verified_users");
+
+    for (verified, state) in [(true, VerificationState::Verified), (false, VerificationState::Expired)] {
+        let query = doc! {
+            "state": { "$exists": false },
+            "verified": verified,
+        };
+        let update = doc! {
+            "$set": {
+                "state": state.as_str(),
+                "transitions": [transition_doc(None, state, "migrated from an older version")],
+            },
+            "$unset": {
+                "verified": "",
+            },
+        };
+
+        match coll.update_many(query, update).await {
+            Ok(r) if r.modified_count > 0 => info!("Migrated {} links to the state `{}`", r.modified_count, state),
+            Ok(_) => {}
+            Err(e) => error!("Cannot migrate links: {}", e),
+        }
+    }
+
+    // Older versions only knew a single link per member, so every verified link is primary.
+    for (query, primary) in [
+        (doc! { "primary": { "$exists": false }, "state": VerificationState::Verified.as_str() }, true),
+        (doc! { "primary": { "$exists": false } }, false),
+    ] {
+        match coll.update_many(query, doc! { "$set": { "primary": primary } }).await {
+            Ok(r) if r.modified_count > 0 => info!("Migrated {} links to primary `{}`", r.modified_count, primary),
+            Ok(_) => {}
+            Err(e) => error!("Cannot migrate links: {}", e),
+        }
+    }
+
+    // Older versions only knew a single hotel.
+    let settings = crate::settings();
+    let hotel = settings.get_default_hotel().get_id();
+    for name in ["verified_users", "pending_verifications"] {
+        let coll = crate::mongo::get_coll::<Document>(name);
+        let query = doc! {
+            "hotel": { "$exists": false },
+        };
+        let update = doc! {
+            "$set": {
+                "hotel": hotel,
+            },
+        };
+
+        match coll.update_many(query, update).await {
+            Ok(r) if r.modified_count > 0 => info!("Migrated {} documents of `{}` to the hotel `{}`", r.modified_count, name, hotel),
+            Ok(_) => {}
+            Err(e) => error!("Cannot migrate `{}`: {}", name, e),
+        }
+    }
+}
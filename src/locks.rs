@@ -0,0 +1,108 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use serenity::all::UserId;
+use tokio::time::{Duration, Instant};
+
+#[derive(Clone, Hash, PartialEq, Eq)]
+pub enum Key {
+    User(UserId),
+    /// A Habbo of a hotel by its lowercase name or its unique id.
+    Habbo(String),
+}
+
+impl Key {
+    pub fn habbo(hotel: &str, name: &str) -> Self {
+        Self::Habbo(format!("{}:{}", hotel, name.to_lowercase()))
+    }
+
+    pub fn habbo_id(hotel: &str, unique_id: &str) -> Self {
+        Self::Habbo(format!("{}:{}", hotel, unique_id))
+    }
+}
+
+pub enum Denied {
+    Locked(Key),
+    Cooldown(Key, Duration),
+}
+
+#[derive(Default)]
+struct State {
+    locked: HashSet<Key>,
+    cooldowns: HashMap<Key, Instant>,
+}
+
+fn state() -> &'static Mutex<State> {
+    use std::sync::OnceLock;
+    static VAL: OnceLock<Mutex<State>> = OnceLock::new();
+    VAL.get_or_init(Default::default)
+}
+
+/// Holds the keys until it is dropped.
+pub struct Lock(Vec<Key>);
+
+impl Lock {
+    /// Locks all keys or none of them.
+    pub fn acquire(keys: Vec<Key>) -> Result<Self, Denied> {
+        let mut lock = Self(Vec::with_capacity(keys.len()));
+        lock.extend(keys)?;
+        Ok(lock)
+    }
+
+    /// Locks additional keys, e.g. once the unique id of a Habbo is known.
+    pub fn extend(&mut self, keys: Vec<Key>) -> Result<(), Denied> {
+        let mut state = state().lock().unwrap();
+
+        let now = Instant::now();
+        state.cooldowns.retain(|_, until| *until > now);
+
+        for key in &keys {
+            if self.0.contains(key) {
+                continue;
+            }
+            if state.locked.contains(key) {
+                return Err(Denied::Locked(key.clone()));
+            }
+            if let Some(until) = state.cooldowns.get(key) {
+                return Err(Denied::Cooldown(key.clone(), *until - now));
+            }
+        }
+
+        for key in keys {
+            if !self.0.contains(&key) {
+                state.locked.insert(key.clone());
+                self.0.push(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Prevents new attempts for the keys of this lock after a failed attempt.
+    pub fn cooldown(&self) {
+        let settings = crate::settings();
+        let verification = settings.get_verification();
+        let mut state = state().lock().unwrap();
+
+        let now = Instant::now();
+        for key in &self.0 {
+            let secs = match key {
+                Key::User(_) => verification.get_user_cooldown(),
+                Key::Habbo(_) => verification.get_habbo_cooldown(),
+            };
+            if secs > 0 {
+                state.cooldowns.insert(key.clone(), now + Duration::from_secs(secs));
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let mut state = state().lock().unwrap();
+        for key in &self.0 {
+            state.locked.remove(key);
+        }
+    }
+}
@@ -1,15 +1,30 @@
+#[macro_export]
+macro_rules! require_guild {
+    ($interaction:expr) => {
+        match $interaction.guild_id {
+            Some(r) => r,
+            None => {
+                return format!(
+                    "Hello <@{}> :)\n\nThis command can only be used in a server!",
+                    $interaction.user.id
+                );
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! check_role_available {
-    () => {
-        if $crate::settings().get_guild().get_verify_role_id() < $crate::LOWEST_ID {
+    ($config:expr) => {
+        if $config.verify_role_id < $crate::LOWEST_ID {
             return format!(
                 "No role has been set for verified users! Use the command `/init`!",
             );
         }
     };
 
-    ($user_id:expr) => {
-        if $crate::settings().get_guild().get_verify_role_id() < $crate::LOWEST_ID {
+    ($config:expr, $user_id:expr) => {
+        if $config.verify_role_id < $crate::LOWEST_ID {
             return format!(
                 "Hello <@{}> :)\n\nNo role has been set for verified users! Use the command `/init`!",
                 $user_id
@@ -17,18 +32,34 @@ macro_rules! check_role_available {
         }
     };
 
-    ($http:expr, $user_id:expr) => {
-        $crate::check_role_available!($user_id);
+    ($http:expr, $guild_id:expr, $config:expr, $user_id:expr) => {
+        $crate::check_role_available!($config, $user_id);
 
-        let settings = $crate::settings();
-        let (role_id, guild_id) = (settings.get_guild().get_verify_role_id(), settings.get_guild().get_id());
-        let roles = $http.get_guild_roles(guild_id.into()).await.unwrap();
+        let role_id = $config.verify_role_id;
+        let roles = $http.get_guild_roles($guild_id).await.unwrap_or_default();
         if !roles.iter().any(|role| role.id.get() == role_id) {
             return format!(
-                "Hello <@{}> :)\n\nThe role with the ID `{}` does not exist! Please remove `verify_role_id` from the `settings.json` file, restart the bot and execute the command `/init` again!",
+                "Hello <@{}> :)\n\nThe role with the ID `{}` does not exist anymore! Please execute the command `/init` again!",
                 $user_id,
                 role_id
             );
         }
     };
-}
\ No newline at end of file
+}
+
+#[macro_export]
+macro_rules! check_admin {
+    ($interaction:expr) => {
+        let is_admin = $interaction
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions)
+            .is_some_and(|permissions| permissions.administrator());
+        if !is_admin {
+            return format!(
+                "Hello <@{}> :)\n\nYou are not allowed to execute this command!",
+                $interaction.user.id
+            );
+        }
+    };
+}
@@ -13,20 +13,32 @@ use serenity::{
 use tokio::runtime::{Builder, Runtime};
 
 // MOD
+mod accounts;
+mod cli;
 mod commands;
+mod config;
+mod conflicts;
+mod events;
+mod guilds;
+mod habbo_api;
 mod helper;
+mod jobs;
+mod links;
+mod locks;
 mod macros;
 mod mongo;
+mod nicknames;
+mod roles;
+mod schema;
 mod structs;
+mod validation;
 
-pub const LOOKUP_URL: &str = "https://origins.habbo.com/api/public/users?name=";
 // https://discord.com/developers/docs/reference#snowflakes
 pub const LOWEST_ID: u64 = 10000000000000000;
 
-pub fn settings() -> &'static structs::Settings {
-    use std::sync::OnceLock;
-    static VAL: OnceLock<structs::Settings> = OnceLock::new();
-    VAL.get_or_init(structs::Settings::load)
+/// Returns a snapshot of the current settings, see `config::get`.
+pub fn settings() -> std::sync::Arc<structs::Settings> {
+    config::get()
 }
 
 fn custom_panic() {
@@ -53,7 +65,8 @@ fn custom_panic() {
 }
 
 pub fn custom_runtime() -> Runtime {
-    let settings_threads = settings().get_threads();
+    let settings = settings();
+    let settings_threads = settings.get_threads();
     match Builder::new_multi_thread()
         .worker_threads(settings_threads.get_count() as usize)
         .thread_name("origins-runtime-worker") // Default: "tokio-runtime-worker"
@@ -68,12 +81,22 @@ pub fn custom_runtime() -> Runtime {
 }
 
 fn main() {
-    if log4rs::init_file("logging.yaml", Default::default()).is_err() {
-        panic!("`logging_config.yaml` is missing");
+    let args = cli::args();
+    if args.check_config {
+        std::process::exit(validation::check_config());
+    }
+
+    if log4rs::init_file(&args.logging, Default::default()).is_err() {
+        panic!("`{}` is missing", args.logging);
     }
 
     custom_panic();
 
+    let problems = validation::validate(&settings());
+    if !problems.is_empty() {
+        panic!("{}", validation::report(&problems));
+    }
+
     custom_runtime().block_on(async {
         start().await;
     });
@@ -82,12 +105,20 @@ fn main() {
 pub async fn start() {
     println!("Connecting to MongoDB...");
     mongo::init().await;
+    schema::init().await;
+    config::watch();
 
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES;
+    let mut intents = GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES;
+    // Reverting nicknames needs the privileged intent, which has to be enabled in the Discord developer portal.
+    // Bots without nickname templates keep working without it.
+    if guilds::uses_nicknames().await {
+        info!("Nickname templates are used, requesting the privileged intent `GUILD_MEMBERS`");
+        intents |= GatewayIntents::GUILD_MEMBERS;
+    }
 
-    let token = settings().get_token();
-    let mut client = match Client::builder(token, intents)
+    let settings = settings();
+    let mut client = match Client::builder(settings.get_token(), intents)
         .event_handler(structs::Handler)
         .activity(ActivityData::playing("Habbo Hotel:Origins"))
         .status(OnlineStatus::DoNotDisturb)
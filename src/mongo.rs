@@ -1,4 +1,7 @@
-use mongodb::{options::ClientOptions, Client, Collection};
+use mongodb::{
+    options::{ClientOptions, Collation, CollationStrength},
+    Client, Collection,
+};
 
 pub fn client(opts: Option<ClientOptions>) -> &'static Client {
     use std::sync::OnceLock;
@@ -34,3 +37,11 @@ where
     let db = client.database(mdb.get_database());
     db.collection::<T>(name)
 }
+
+/// Compares Habbo names case-insensitively, "Foo" and "foo" are the same Habbo.
+pub fn habbo_collation() -> Collation {
+    Collation::builder()
+        .locale("en")
+        .strength(CollationStrength::Secondary)
+        .build()
+}
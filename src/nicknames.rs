@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use serenity::all::{EditMember, GuildId, GuildMemberUpdateEvent, Http, RoleId, User, UserId};
+
+use crate::structs::VerificationState;
+
+/// Discord limits nicknames to 32 characters.
+const MAX_LEN: usize = 32;
+
+/// Habbo names are at most 15 characters long.
+const MAX_HABBO_LEN: usize = 15;
+
+/// Whether the template contains `{habbo}` and leaves enough room for every Habbo name.
+pub fn is_valid(template: &str) -> bool {
+    let rest = template.replace("{habbo}", "").replace("{discord}", "");
+    template.contains("{habbo}") && rest.chars().count() + MAX_HABBO_LEN <= MAX_LEN
+}
+
+/// Replaces `{habbo}` and `{discord}` in the template. The Discord name is shortened
+/// if the nickname would be too long, so the Habbo name is always complete.
+pub fn render(template: &str, discord: &str, habbo: &str) -> String {
+    let nick = template.replace("{habbo}", habbo);
+    let count = nick.matches("{discord}").count();
+    if count == 0 {
+        return nick.chars().take(MAX_LEN).collect();
+    }
+
+    let room = MAX_LEN.saturating_sub(nick.replace("{discord}", "").chars().count()) / count;
+    let discord = discord.chars().take(room).collect::<String>();
+    nick.replace("{discord}", discord.trim_end()).chars().take(MAX_LEN).collect()
+}
+
+/// Discord does not allow bots to change the nickname of the owner
+/// or of members whose highest role is at or above the highest role of the bot.
+async fn editable(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, member_roles: &[RoleId]) -> bool {
+    let (guild, roles, top) = match (
+        http.get_guild(guild_id).await,
+        http.get_guild_roles(guild_id).await,
+        crate::roles::top_position(http, guild_id).await,
+    ) {
+        (Ok(guild), Ok(roles), Some(top)) => (guild, roles, top),
+        _ => return false,
+    };
+    if guild.owner_id == user_id {
+        return false;
+    }
+
+    roles
+        .iter()
+        .filter(|role| member_roles.contains(&role.id))
+        .all(|role| role.position < top)
+}
+
+/// Changes the nickname of the member from `current` to `nick`.
+async fn apply(http: &Arc<Http>, guild_id: GuildId, user: &User, roles: &[RoleId], current: Option<&str>, nick: Option<String>) {
+    if current == nick.as_deref() {
+        return;
+    }
+    if !editable(http, guild_id, user.id, roles).await {
+        warn!(
+            "Cannot change the nickname of the Discord user {} in the guild {}: the member is the owner or has a role above mine",
+            user.id,
+            guild_id,
+        );
+        return;
+    }
+
+    // An empty nickname removes it.
+    let builder = EditMember::new()
+        .nickname(nick.unwrap_or_default())
+        .audit_log_reason("Habbo link");
+    if let Err(e) = guild_id.edit_member(http, user.id, builder).await {
+        error!("Cannot change the nickname of the Discord user {}: {}", user.id, e);
+    }
+}
+
+/// Returns the nickname template of the guild and the primary Habbo of the member,
+/// or `None` if the guild has no template or the member is not verified.
+async fn template(guild_id: GuildId, user_id: UserId) -> Option<(String, Box<str>)> {
+    let template = crate::guilds::get(guild_id).await.nickname_template;
+    if template.is_empty() {
+        return None;
+    }
+
+    let query = doc! {
+        "id": user_id.to_string(),
+        "guild": guild_id.to_string(),
+        "state": VerificationState::Verified.as_str(),
+        "primary": true,
+    };
+    match crate::links::coll().find_one(query).await {
+        Ok(r) => r.map(|link| (template, link.habbo)),
+        Err(e) => {
+            error!("{}", e);
+            None
+        }
+    }
+}
+
+fn nickname(template: &str, user: &User, habbo: &str) -> String {
+    let discord = user.global_name.as_deref().unwrap_or(&user.name);
+    render(template, discord, habbo)
+}
+
+/// Sets the nickname of a verified member to the nickname template of the guild, filled with the primary Habbo.
+/// Does nothing if the guild has no template or the member is not verified.
+pub async fn sync(http: &Arc<Http>, guild_id: GuildId, user_id: UserId) {
+    let (template, habbo) = match template(guild_id, user_id).await {
+        Some(r) => r,
+        None => return,
+    };
+    let member = match http.get_member(guild_id, user_id).await {
+        Ok(r) => r,
+        // The member has left the guild.
+        Err(_) => return,
+    };
+
+    let nick = nickname(&template, &member.user, &habbo);
+    apply(http, guild_id, &member.user, &member.roles, member.nick.as_deref(), Some(nick)).await;
+}
+
+/// Reverts the nickname of a verified member who changed it, see `Handler::guild_member_update`.
+/// Updates which already carry the nickname, e.g. our own changes, do not send any request to Discord.
+pub async fn revert(http: &Arc<Http>, event: &GuildMemberUpdateEvent) {
+    if let Some((template, habbo)) = template(event.guild_id, event.user.id).await {
+        let nick = nickname(&template, &event.user, &habbo);
+        apply(http, event.guild_id, &event.user, &event.roles, event.nick.as_deref(), Some(nick)).await;
+    }
+}
+
+/// Removes the nickname of the member, e.g. after the link has been removed.
+/// Does nothing if the guild has no template, so nicknames set by the member are kept.
+pub async fn clear(http: &Arc<Http>, guild_id: GuildId, user_id: UserId) {
+    if crate::guilds::get(guild_id).await.nickname_template.is_empty() {
+        return;
+    }
+
+    if let Ok(member) = http.get_member(guild_id, user_id).await {
+        apply(http, guild_id, &member.user, &member.roles, member.nick.as_deref(), None).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_templates() {
+        assert!(is_valid("{habbo}"));
+        assert!(is_valid("{discord} | {habbo}"));
+        assert!(is_valid("[Origins] {habbo}"));
+        // 17 characters plus the longest Habbo name fill the nickname completely.
+        assert!(is_valid(&format!("{}{{habbo}}", "x".repeat(MAX_LEN - MAX_HABBO_LEN))));
+    }
+
+    #[test]
+    fn invalid_templates() {
+        assert!(!is_valid(""));
+        assert!(!is_valid("{discord}"));
+        assert!(!is_valid("{Habbo}"));
+        assert!(!is_valid(&format!("{}{{habbo}}", "x".repeat(MAX_LEN - MAX_HABBO_LEN + 1))));
+        assert!(!is_valid("This template is much longer than 32 characters {habbo}"));
+    }
+
+    #[test]
+    fn render_habbo() {
+        assert_eq!(render("{habbo}", "Discord", "Habbo"), "Habbo");
+        assert_eq!(render("[HHO] {habbo}", "Discord", "Habbo"), "[HHO] Habbo");
+        assert_eq!(render("{habbo} ({habbo})", "Discord", "Habbo"), "Habbo (Habbo)");
+    }
+
+    #[test]
+    fn render_shortens_the_discord_name() {
+        assert_eq!(render("{discord} | {habbo}", "Discord", "Habbo"), "Discord | Habbo");
+
+        let nick = render("{discord} | {habbo}", &"d".repeat(40), "LongestHabbo123");
+        assert_eq!(nick.chars().count(), MAX_LEN);
+        assert!(nick.ends_with(" | LongestHabbo123"));
+
+        // Trailing spaces of the shortened name are removed.
+        let nick = render("{discord} | {habbo}", "Some very big name", "LongestHabbo123");
+        assert_eq!(nick, "Some very big | LongestHabbo123");
+    }
+
+    #[test]
+    fn render_never_exceeds_the_limit() {
+        let template = "This template is much longer than 32 characters {discord} {habbo}";
+        let nick = render(template, "Discord", "Habbo");
+        assert_eq!(nick.chars().count(), MAX_LEN);
+        assert_eq!(nick, "This template is much longer tha");
+
+        assert_eq!(render("{habbo}", "Discord", &"h".repeat(40)).chars().count(), MAX_LEN);
+        assert_eq!(render("{discord}{discord}{habbo}", "ÄÖÜäöü", "Habbo"), "ÄÖÜäöüÄÖÜäöüHabbo");
+    }
+}
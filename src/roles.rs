@@ -0,0 +1,197 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    all::{GuildId, Http, RoleId, UserId},
+    futures::TryStreamExt,
+};
+
+use crate::structs::{Profile, VerificationState};
+
+/// Grants the role `role` to verified members wearing the badge `badge`.
+#[derive(Serialize, Deserialize)]
+pub struct BadgeRole {
+    pub guild: String,
+    pub badge: Box<str>,
+    pub role: String,
+}
+
+pub fn badge_coll() -> mongodb::Collection<BadgeRole> {
+    crate::mongo::get_coll("badge_roles")
+}
+
+/// Badge codes are compared in uppercase, e.g. `ADM`.
+pub fn normalize_badge(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+pub async fn badge_roles(guild_id: GuildId) -> Vec<BadgeRole> {
+    let query = doc! {
+        "guild": guild_id.to_string(),
+    };
+    match badge_coll().find(query).await {
+        Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+        Err(e) => {
+            error!("Cannot load badge roles: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The position of the highest role of the bot. Roles at or above it cannot be given by the bot.
+pub async fn top_position(http: &Arc<Http>, guild_id: GuildId) -> Option<u16> {
+    let bot = http.get_current_user().await.ok()?;
+    let member = http.get_member(guild_id, bot.id).await.ok()?;
+    let roles = http.get_guild_roles(guild_id).await.ok()?;
+    roles
+        .iter()
+        .filter(|role| member.roles.contains(&role.id))
+        .map(|role| role.position)
+        .max()
+}
+
+/// Adds the roles in `wanted` which are `true` and removes the ones which are `false`.
+/// Only roles the member does not have (or has) are changed.
+async fn apply(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, wanted: HashMap<RoleId, bool>) {
+    if wanted.is_empty() {
+        return;
+    }
+
+    let member = match http.get_member(guild_id, user_id).await {
+        Ok(r) => r,
+        // The member has left the guild.
+        Err(_) => return,
+    };
+
+    for (role_id, want) in wanted {
+        let has = member.roles.contains(&role_id);
+        let result = match (want, has) {
+            (true, false) => http.add_member_role(guild_id, user_id, role_id, Some("Habbo link")).await,
+            (false, true) => http.remove_member_role(guild_id, user_id, role_id, Some("Habbo link")).await,
+            _ => continue,
+        };
+        if let Err(e) = result {
+            error!("Cannot update the role {} of the Discord user {}: {}", role_id, user_id, e);
+        }
+    }
+}
+
+/// Gives the member the roles of the badges selected in the Habbo profile and removes all others.
+pub async fn sync_badges(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, profile: &Profile) {
+    if !crate::guilds::get(guild_id).await.badge_roles_enabled {
+        return;
+    }
+
+    let badges = profile
+        .selected_badges
+        .iter()
+        .map(|badge| normalize_badge(&badge.code))
+        .collect::<HashSet<_>>();
+
+    // Several badges can grant the same role.
+    let mut wanted = HashMap::new();
+    for badge_role in badge_roles(guild_id).await {
+        if let Ok(role_id) = badge_role.role.parse::<u64>() {
+            *wanted.entry(RoleId::new(role_id)).or_insert(false) |= badges.contains(&*badge_role.badge);
+        }
+    }
+
+    apply(http, guild_id, user_id, wanted).await;
+}
+
+/// Removes all badge roles, e.g. after the link has been removed.
+pub async fn strip_badges(http: &Arc<Http>, guild_id: GuildId, user_id: UserId) {
+    let wanted = badge_roles(guild_id)
+        .await
+        .into_iter()
+        .filter_map(|badge_role| badge_role.role.parse::<u64>().ok())
+        .map(|role_id| (RoleId::new(role_id), false))
+        .collect();
+
+    apply(http, guild_id, user_id, wanted).await;
+}
+
+/// Removes the roles from every verified member of the guild, e.g. after their badge roles have been removed.
+/// Runs in the background, since every member has to be loaded.
+pub fn strip_from_members(http: &Arc<Http>, guild_id: GuildId, roles: HashSet<RoleId>) {
+    if roles.is_empty() {
+        return;
+    }
+
+    let http = Arc::clone(http);
+    tokio::spawn(async move {
+        let query = doc! {
+            "guild": guild_id.to_string(),
+            "state": VerificationState::Verified.as_str(),
+        };
+        let ids = match crate::links::coll().distinct("id", query).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("Cannot load the verified members of the guild {}: {}", guild_id, e);
+                return;
+            }
+        };
+
+        for user_id in ids.iter().filter_map(|id| id.as_str()?.parse::<u64>().ok()) {
+            let wanted = roles.iter().map(|role_id| (*role_id, false)).collect();
+            apply(&http, guild_id, UserId::new(user_id), wanted).await;
+        }
+    });
+}
+
+/// Gives the member every age role the Habbo has reached and removes all others.
+pub async fn sync_age(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, member_since: bson::DateTime) {
+    let config = crate::guilds::get(guild_id).await;
+    if !config.age_roles_enabled {
+        return;
+    }
+
+    let mut wanted = HashMap::new();
+    for age_role in config.age_roles {
+        if age_role.get_role_id() < crate::LOWEST_ID {
+            continue;
+        }
+        let role_id = RoleId::new(age_role.get_role_id());
+        *wanted.entry(role_id).or_insert(false) |= age_role.matches(member_since);
+    }
+
+    apply(http, guild_id, user_id, wanted).await;
+}
+
+/// Removes all age roles, e.g. after the link has been removed.
+pub async fn strip_age(http: &Arc<Http>, guild_id: GuildId, user_id: UserId) {
+    let wanted = crate::guilds::get(guild_id)
+        .await
+        .age_roles
+        .iter()
+        .filter(|age_role| age_role.get_role_id() >= crate::LOWEST_ID)
+        .map(|age_role| (RoleId::new(age_role.get_role_id()), false))
+        .collect();
+
+    apply(http, guild_id, user_id, wanted).await;
+}
+
+/// Gives the member the presence role while the Habbo is in the hotel.
+pub async fn sync_presence(http: &Arc<Http>, guild_id: GuildId, user_id: UserId, online: bool) {
+    let config = crate::guilds::get(guild_id).await;
+    if config.presence_role_id < crate::LOWEST_ID || !config.presence_role_enabled {
+        return;
+    }
+    let role_id = config.presence_role_id;
+
+    apply(http, guild_id, user_id, HashMap::from([(RoleId::new(role_id), online)])).await;
+}
+
+/// Removes the verify role and all roles granted for the Habbo, e.g. after the link has been removed.
+pub async fn strip_all(http: &Arc<Http>, guild_id: GuildId, user_id: UserId) {
+    let role_id = crate::guilds::get(guild_id).await.verify_role_id;
+    if role_id >= crate::LOWEST_ID {
+        let _ = http.remove_member_role(guild_id, user_id, RoleId::new(role_id), None).await;
+    }
+    strip_badges(http, guild_id, user_id).await;
+    strip_age(http, guild_id, user_id).await;
+    sync_presence(http, guild_id, user_id, false).await;
+}
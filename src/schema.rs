@@ -0,0 +1,145 @@
+use bson::Document;
+use mongodb::{options::IndexOptions, IndexModel};
+use serenity::futures::TryStreamExt;
+
+struct Index {
+    collection: &'static str,
+    name: &'static str,
+    keys: Document,
+    unique: bool,
+    /// Only documents matching this filter are indexed.
+    partial: Option<Document>,
+    collation: bool,
+}
+
+impl Index {
+    fn new(collection: &'static str, name: &'static str, keys: Document) -> Self {
+        Self {
+            collection,
+            name,
+            keys,
+            unique: false,
+            partial: None,
+            collation: false,
+        }
+    }
+
+    fn unique(mut self, partial: Option<Document>) -> Self {
+        self.unique = true;
+        self.partial = partial;
+        self
+    }
+
+    fn collation(mut self) -> Self {
+        self.collation = true;
+        self
+    }
+
+    fn model(&self) -> IndexModel {
+        let options = IndexOptions::builder()
+            .name(self.name.to_string())
+            .unique(self.unique.then_some(true))
+            .partial_filter_expression(self.partial.clone())
+            .collation(self.collation.then(crate::mongo::habbo_collation))
+            .build();
+        IndexModel::builder()
+            .keys(self.keys.clone())
+            .options(options)
+            .build()
+    }
+
+    /// Returns up to 10 values which occur more than once and prevent the unique index.
+    async fn duplicates(&self) -> Vec<String> {
+        let coll = crate::mongo::get_coll::<Document>(self.collection);
+
+        let mut group = Document::new();
+        for key in self.keys.keys() {
+            group.insert(key, format!("${}", key));
+        }
+        let pipeline = [
+            doc! { "$match": self.partial.clone().unwrap_or_default() },
+            doc! { "$group": { "_id": group, "count": { "$sum": 1 } } },
+            doc! { "$match": { "count": { "$gt": 1 } } },
+            doc! { "$limit": 10 },
+        ];
+
+        let mut result = Vec::new();
+        let aggregate = coll.aggregate(pipeline);
+        let aggregate = match self.collation {
+            true => aggregate.collation(crate::mongo::habbo_collation()),
+            false => aggregate,
+        };
+        if let Ok(mut cursor) = aggregate.await {
+            while let Ok(Some(d)) = cursor.try_next().await {
+                if let Ok(id) = d.get_document("_id") {
+                    result.push(id.to_string());
+                }
+            }
+        }
+        result
+    }
+
+    async fn create(&self) {
+        let coll = crate::mongo::get_coll::<Document>(self.collection);
+        let e = match coll.create_index(self.model()).await {
+            Ok(_) => return,
+            Err(e) => e,
+        };
+
+        let duplicates = if self.unique {
+            self.duplicates().await
+        } else {
+            Vec::new()
+        };
+
+        if duplicates.is_empty() {
+            error!("Cannot create the index `{}` on `{}`: {}", self.name, self.collection, e);
+            return;
+        }
+
+        error!(
+            "Cannot create the unique index `{}` on `{}` because of conflicting data from an older version.\n> The following values exist more than once: {}\n> Remove the duplicates from the database and restart the bot. Until then, duplicates are not prevented!",
+            self.name,
+            self.collection,
+            duplicates.join(", "),
+        );
+    }
+}
+
+/// Migrates documents of older versions and creates all indexes.
+/// Problems are logged, so the bot can still start with legacy data.
+pub async fn init() {
+    crate::links::migrate().await;
+    crate::guilds::migrate().await;
+
+    let indexes = [
+        // One link per Discord user, guild and Habbo.
+        Index::new("verified_users", "guild_id_hotel_unique_id_unique", doc! { "guild": 1, "id": 1, "hotel": 1, "unique_id": 1 }).unique(Some(doc! {
+            "unique_id": { "$type": "string" },
+        })),
+        // One primary Habbo per Discord user and guild.
+        Index::new("verified_users", "guild_id_primary_verified", doc! { "guild": 1, "id": 1 }).unique(Some(doc! {
+            "primary": true,
+            "state": crate::structs::VerificationState::Verified.as_str(),
+        })),
+        // A Habbo can only be verified by one Discord user per guild.
+        Index::new("verified_users", "guild_hotel_unique_id_verified", doc! { "guild": 1, "hotel": 1, "unique_id": 1 }).unique(Some(doc! {
+            "state": crate::structs::VerificationState::Verified.as_str(),
+            "unique_id": { "$type": "string" },
+        })),
+        Index::new("verified_users", "guild_hotel_habbo_ci", doc! { "guild": 1, "hotel": 1, "habbo": 1 }).collation(),
+        // Pending verifications do not expire in the database, `commands::verify::resume`
+        // removes the ones which expired while the bot was offline and tells the users.
+        Index::new("pending_verifications", "id_unique", doc! { "id": 1 }).unique(None),
+        // Claims which have not been reviewed in time are expired by `jobs::claims`.
+        Index::new("claims", "created_at", doc! { "created_at": 1 }),
+        Index::new("guild_configs", "guild_id_unique", doc! { "guild_id": 1 }).unique(None),
+        // The history of a member and of a Habbo.
+        Index::new("link_events", "guild_user_at", doc! { "guild": 1, "user": 1, "at": -1 }),
+        Index::new("link_events", "guild_hotel_unique_id_at", doc! { "guild": 1, "hotel": 1, "unique_id": 1, "at": -1 }),
+    ];
+
+    for index in &indexes {
+        index.create().await;
+    }
+}
@@ -2,54 +2,119 @@ use std::{fs::File, io::Write};
 
 use serde::{Deserialize, Serialize};
 use serenity::{
-    all::{GuildId, Interaction, Ready},
+    all::{CreateCommand, GuildId, Interaction, Ready},
     async_trait,
     prelude::{Context, EventHandler},
 };
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct MongoDB {
+    #[serde(default)]
     uri: Box<str>,
+    #[serde(default)]
     database: Box<str>,
 }
 
 impl MongoDB {
     #[inline(always)]
-    pub fn get_uri(&'static self) -> &'static str {
+    pub fn get_uri(&self) -> &str {
         &self.uri
     }
 
     #[inline(always)]
-    pub fn get_database(&'static self) -> &'static str {
+    pub fn get_database(&self) -> &str {
         &self.database
     }
 }
 
-#[derive(Deserialize, Serialize)]
+/// A role for verified members whose Habbo is old enough.
+/// If both conditions are set, both must be met.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct AgeRole {
+    role_id: u64,
+    /// Minimum age of the Habbo in days, e.g. `365` for "1 year+".
+    #[serde(default)]
+    min_days: u64,
+    /// The Habbo must have been created before this RFC 3339 date, e.g. for "Member since launch week".
+    #[serde(default)]
+    joined_before: Option<Box<str>>,
+}
+
+impl AgeRole {
+    pub fn new(role_id: u64, min_days: u64, joined_before: Option<&str>) -> Self {
+        Self {
+            role_id,
+            min_days,
+            joined_before: joined_before.map(Into::into),
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_role_id(&self) -> u64 {
+        self.role_id
+    }
+
+    #[inline(always)]
+    pub fn get_min_days(&self) -> u64 {
+        self.min_days
+    }
+
+    #[inline(always)]
+    pub fn get_joined_before(&self) -> Option<&str> {
+        self.joined_before.as_deref()
+    }
+
+    /// Whether a Habbo created at `member_since` deserves this role.
+    pub fn matches(&self, member_since: bson::DateTime) -> bool {
+        const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+        let age_days = (bson::DateTime::now().timestamp_millis() - member_since.timestamp_millis()) / DAY_MILLIS;
+        if age_days < self.min_days as i64 {
+            return false;
+        }
+
+        match self.joined_before.as_deref().map(bson::DateTime::parse_rfc3339_str) {
+            Some(Ok(date)) => member_since < date,
+            Some(Err(_)) => false,
+            None => true,
+        }
+    }
+}
+
+/// The guild of older versions, which only supported a single guild.
+/// It is moved to `guild_configs` once, see `guilds::migrate`.
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Guild {
     id: u64,
     #[serde(default)]
     verify_role_id: u64,
+    #[serde(default)]
+    age_roles: Vec<AgeRole>,
+    #[serde(default)]
+    presence_role_id: u64,
+    #[serde(default)]
+    log_channel_id: u64,
 }
 
 impl Guild {
     #[inline(always)]
-    pub fn get_id(&'static self) -> u64 {
+    pub fn get_id(&self) -> u64 {
         self.id
     }
 
-    #[inline(always)]
-    pub fn get_verify_role_id(&'static self) -> u64 {
-        self.verify_role_id
-    }
-
-    #[inline(always)]
-    pub fn set_verify_role_id(&'static mut self, val: u64) {
-        self.verify_role_id = val;
+    pub fn to_config(&self) -> GuildConfig {
+        GuildConfig {
+            guild_id: self.id.to_string(),
+            verify_role_id: self.verify_role_id,
+            age_roles: self.age_roles.clone(),
+            presence_role_id: self.presence_role_id,
+            log_channel_id: self.log_channel_id,
+            ..Default::default()
+        }
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Threads {
     count: u16,
     stack_size: usize,
@@ -57,81 +122,433 @@ pub struct Threads {
 
 impl Threads {
     #[inline(always)]
-    pub fn get_count(&'static self) -> u16 {
+    pub fn get_count(&self) -> u16 {
         self.count
     }
 
     #[inline(always)]
-    pub fn get_stack_size(&'static self) -> usize {
+    pub fn get_stack_size(&self) -> usize {
         self.stack_size
     }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Verification {
+    /// Seconds between two lookups of the Habbo profile.
+    #[serde(default = "Verification::default_interval")]
+    interval: u64,
+    /// Seconds the user has to change the motto.
+    #[serde(default = "Verification::default_window")]
+    window: u64,
+    /// Seconds a user has to wait after a failed verification.
+    #[serde(default = "Verification::default_user_cooldown")]
+    user_cooldown: u64,
+    /// Seconds nobody can verify a Habbo after a failed verification of it.
+    #[serde(default = "Verification::default_habbo_cooldown")]
+    habbo_cooldown: u64,
+    /// The code the motto has to be changed to. Every `#` is replaced by a random letter or digit.
+    #[serde(default = "Verification::default_code_format")]
+    code_format: Box<str>,
+}
+
+impl Verification {
+    fn default_interval() -> u64 {
+        10
+    }
+
+    fn default_window() -> u64 {
+        120
+    }
+
+    fn default_user_cooldown() -> u64 {
+        60
+    }
+
+    fn default_habbo_cooldown() -> u64 {
+        30
+    }
+
+    fn default_code_format() -> Box<str> {
+        "#####".into()
+    }
+
+    #[inline(always)]
+    pub fn get_interval(&self) -> u64 {
+        self.interval
+    }
+
+    #[inline(always)]
+    pub fn get_window(&self) -> u64 {
+        self.window
+    }
+
+    #[inline(always)]
+    pub fn set_window(&mut self, val: u64) {
+        self.window = val;
+    }
+
+    #[inline(always)]
+    pub fn reset_window(&mut self) {
+        self.window = Self::default_window();
+    }
+
+    #[inline(always)]
+    pub fn get_user_cooldown(&self) -> u64 {
+        self.user_cooldown
+    }
+
+    #[inline(always)]
+    pub fn get_habbo_cooldown(&self) -> u64 {
+        self.habbo_cooldown
+    }
+
+    #[inline(always)]
+    pub fn get_code_format(&self) -> &str {
+        &self.code_format
+    }
+
+    #[inline(always)]
+    pub fn set_code_format(&mut self, val: &str) {
+        self.code_format = val.into();
+    }
+
+    #[inline(always)]
+    pub fn reset_code_format(&mut self) {
+        self.code_format = Self::default_code_format();
+    }
+}
+
+impl Default for Verification {
+    fn default() -> Self {
+        Self {
+            interval: Self::default_interval(),
+            window: Self::default_window(),
+            user_cooldown: Self::default_user_cooldown(),
+            habbo_cooldown: Self::default_habbo_cooldown(),
+            code_format: Self::default_code_format(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Hotel {
+    id: Box<str>,
+    name: Box<str>,
+    url: Box<str>,
+}
+
+impl Hotel {
+    fn new(id: &str, name: &str, url: &str) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            url: url.into(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    #[inline(always)]
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline(always)]
+    pub fn get_url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HabboApi {
+    /// Seconds until a request to Habbo Hotel:Origins is cancelled.
+    #[serde(default = "HabboApi::default_timeout")]
+    timeout: u64,
+    /// How often a request is repeated if the hotel is unavailable or rate limits us.
+    #[serde(default = "HabboApi::default_retries")]
+    retries: u32,
+}
+
+impl HabboApi {
+    fn default_timeout() -> u64 {
+        10
+    }
+
+    fn default_retries() -> u32 {
+        2
+    }
+
+    #[inline(always)]
+    pub fn get_timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    #[inline(always)]
+    pub fn get_retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+impl Default for HabboApi {
+    fn default() -> Self {
+        Self {
+            timeout: Self::default_timeout(),
+            retries: Self::default_retries(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Jobs {
+    /// Seconds between two refreshes of the display names of all linked Habbos.
+    #[serde(default = "Jobs::default_rename_interval")]
+    rename_interval: u64,
+    /// Seconds between two syncs of the badge roles of all verified members.
+    #[serde(default = "Jobs::default_badges_interval")]
+    badges_interval: u64,
+    /// Seconds between two promotions of verified members to the age roles they reached.
+    #[serde(default = "Jobs::default_age_interval")]
+    age_interval: u64,
+    /// Seconds between two checks of who is in the hotel.
+    #[serde(default = "Jobs::default_presence_interval")]
+    presence_interval: u64,
+    /// Maximum number of requests per minute the presence check sends to the hotel.
+    #[serde(default = "Jobs::default_presence_budget")]
+    presence_budget: u32,
+    /// Seconds between two checks whether all linked Habbos still exist.
+    #[serde(default = "Jobs::default_revalidate_interval")]
+    revalidate_interval: u64,
+    /// Seconds a flagged link is kept before the verify role is removed, at least one day.
+    #[serde(default = "Jobs::default_revalidate_grace")]
+    revalidate_grace: u64,
+}
+
+impl Jobs {
+    fn default_rename_interval() -> u64 {
+        6 * 60 * 60
+    }
+
+    fn default_badges_interval() -> u64 {
+        60 * 60
+    }
+
+    fn default_age_interval() -> u64 {
+        24 * 60 * 60
+    }
+
+    fn default_presence_interval() -> u64 {
+        5 * 60
+    }
+
+    fn default_presence_budget() -> u32 {
+        60
+    }
+
+    fn default_revalidate_interval() -> u64 {
+        12 * 60 * 60
+    }
+
+    fn default_revalidate_grace() -> u64 {
+        3 * 24 * 60 * 60
+    }
+
+    #[inline(always)]
+    pub fn get_rename_interval(&self) -> u64 {
+        self.rename_interval
+    }
+
+    #[inline(always)]
+    pub fn get_badges_interval(&self) -> u64 {
+        self.badges_interval
+    }
+
+    #[inline(always)]
+    pub fn get_age_interval(&self) -> u64 {
+        self.age_interval
+    }
+
+    #[inline(always)]
+    pub fn get_presence_interval(&self) -> u64 {
+        self.presence_interval
+    }
+
+    #[inline(always)]
+    pub fn get_presence_budget(&self) -> u32 {
+        self.presence_budget
+    }
+
+    #[inline(always)]
+    pub fn get_revalidate_interval(&self) -> u64 {
+        self.revalidate_interval
+    }
+
+    #[inline(always)]
+    pub fn get_revalidate_grace(&self) -> u64 {
+        self.revalidate_grace
+    }
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self {
+            rename_interval: Self::default_rename_interval(),
+            badges_interval: Self::default_badges_interval(),
+            age_interval: Self::default_age_interval(),
+            presence_interval: Self::default_presence_interval(),
+            presence_budget: Self::default_presence_budget(),
+            revalidate_interval: Self::default_revalidate_interval(),
+            revalidate_grace: Self::default_revalidate_grace(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Settings {
+    /// Can be set with `ORIGINSBOT_MONGODB_URI` and `ORIGINSBOT_MONGODB_DATABASE` instead.
+    #[serde(default)]
     mongodb: MongoDB,
-    guild: Guild,
+    /// Only set by older versions, see `Guild`.
+    #[serde(default)]
+    guild: Option<Guild>,
+    /// Can be set with `ORIGINSBOT_TOKEN` instead.
+    #[serde(default)]
     token: Box<str>,
     threads: Threads,
+    #[serde(default)]
+    verification: Verification,
+    /// The first hotel is used if no hotel has been selected.
+    #[serde(default = "Settings::default_hotels")]
+    hotels: Vec<Hotel>,
+    #[serde(default)]
+    habbo_api: HabboApi,
+    #[serde(default)]
+    jobs: Jobs,
 }
 
 impl Settings {
-    pub fn load() -> Self {
-        let file = match File::open("settings.json") {
-            Ok(r) => r,
-            Err(e) => panic!("`settings.json` is missing\n> {}", e),
-        };
+    fn default_hotels() -> Vec<Hotel> {
+        vec![
+            Hotel::new("com", "Habbo Hotel:Origins (.com)", "https://origins.habbo.com"),
+            Hotel::new("es", "Habbo Hotel:Origins (.es)", "https://origins.habbo.es"),
+            Hotel::new("com.br", "Habbo Hotel:Origins (.com.br)", "https://origins.habbo.com.br"),
+        ]
+    }
 
-        let data = match std::io::read_to_string(file) {
-            Ok(r) => r,
-            Err(e) => panic!("`settings.json` could not be read\n> {}", e),
-        };
+    /// Reads and deserializes `path` without applying the environment variables.
+    pub fn read_file(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("`{}` is missing\n> {}", path, e))?;
+        let data = std::io::read_to_string(file).map_err(|e| format!("`{}` could not be read\n> {}", path, e))?;
+        serde_json::from_str(&data).map_err(|e| format!("`{}` could not be deserialized\n> {}", path, e))
+    }
 
-        match serde_json::from_str(&data) {
+    /// Reads `path` and applies the environment variables, see `cli::Args`.
+    pub fn read(path: &str) -> Result<Self, String> {
+        let mut settings = Self::read_file(path)?;
+        settings.apply_env();
+        Ok(settings)
+    }
+
+    /// Overrides values of the settings file with the `ORIGINSBOT_*` environment variables,
+    /// so secrets do not have to be stored in the file.
+    pub fn apply_env(&mut self) {
+        use std::env::var;
+
+        if let Ok(val) = var("ORIGINSBOT_TOKEN") {
+            self.token = val.into();
+        }
+        if let Ok(val) = var("ORIGINSBOT_MONGODB_URI") {
+            self.mongodb.uri = val.into();
+        }
+        if let Ok(val) = var("ORIGINSBOT_MONGODB_DATABASE") {
+            self.mongodb.database = val.into();
+        }
+    }
+
+    pub fn load() -> Self {
+        match Self::read(crate::config::path()) {
             Ok(r) => r,
-            Err(e) => panic!("`settings.json` could not be deserialized\n> {}", e),
+            Err(e) => panic!("{}", e),
         }
     }
 
-    #[inline(always)]
-    pub unsafe fn as_mut(&'static self) -> &'static mut Self {
-        crate::helper::ref_to_refmut(&self)
+    /// Writes the settings to `path`. A temporary file is written first and then renamed,
+    /// so `path` never contains half-written settings, even if the bot crashes.
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let buf = serde_json::to_vec_pretty(self)?;
+
+        let tmp = format!("{}.tmp", path);
+        let mut file = File::create(&tmp)?;
+        file.write_all(&buf)?;
+        file.sync_all()?;
+        drop(file);
+
+        std::fs::rename(&tmp, path)
     }
 
     #[inline(always)]
-    pub fn get_mongodb(&'static self) -> &'static MongoDB {
+    pub fn get_mongodb(&self) -> &MongoDB {
         &self.mongodb
     }
 
     #[inline(always)]
-    pub fn get_guild(&'static self) -> &'static Guild {
-        &self.guild
+    pub fn get_guild(&self) -> Option<&Guild> {
+        self.guild.as_ref()
     }
 
     #[inline(always)]
-    pub unsafe fn get_guild_mut(&'static mut self) -> &'static mut Guild {
-        &mut self.guild
+    pub fn remove_guild(&mut self) {
+        self.guild = None;
     }
 
     #[inline(always)]
-    pub fn get_token(&'static self) -> &'static str {
+    pub fn get_token(&self) -> &str {
         &self.token
     }
 
     #[inline(always)]
-    pub fn get_threads(&'static self) -> &'static Threads {
+    pub fn get_threads(&self) -> &Threads {
         &self.threads
     }
 
-    pub fn save(&'static self) {
-        let buf = serde_json::to_vec_pretty(&self).unwrap();
-        let mut file = File::options()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open("settings.json")
-            .unwrap();
-        let _ = file.write_all(&buf);
+    #[inline(always)]
+    pub fn get_verification(&self) -> &Verification {
+        &self.verification
+    }
+
+    #[inline(always)]
+    pub fn get_verification_mut(&mut self) -> &mut Verification {
+        &mut self.verification
+    }
+
+    #[inline(always)]
+    pub fn get_hotels(&self) -> &[Hotel] {
+        &self.hotels
+    }
+
+    pub fn get_hotel(&self, id: &str) -> Option<&Hotel> {
+        self.hotels.iter().find(|hotel| *hotel.id == *id)
+    }
+
+    /// Falls back to the first of the default hotels if no hotel has been configured.
+    pub fn get_default_hotel(&self) -> &Hotel {
+        use std::sync::OnceLock;
+        static FALLBACK: OnceLock<Hotel> = OnceLock::new();
+        self.hotels
+            .first()
+            .unwrap_or_else(|| FALLBACK.get_or_init(|| Self::default_hotels().swap_remove(0)))
+    }
+
+    #[inline(always)]
+    pub fn get_habbo_api(&self) -> &HabboApi {
+        &self.habbo_api
+    }
+
+    #[inline(always)]
+    pub fn get_jobs(&self) -> &Jobs {
+        &self.jobs
     }
 }
 
@@ -140,50 +557,98 @@ pub struct Handler;
 #[async_trait]
 impl EventHandler for Handler {
     async fn interaction_create(&self, ctx: Context, ia: Interaction) {
-        if let Interaction::Command(command) = ia {
-            let reply_msg = format!("Hello <@{}> :)\n\nRunning ...", command.user.id);
-            crate::helper::reply(&ctx.http, reply_msg, &command).await;
+        match ia {
+            Interaction::Command(command) => {
+                let reply_msg = format!("Hello <@{}> :)\n\nRunning ...", command.user.id);
+                crate::helper::reply(&ctx.http, reply_msg, &command).await;
 
-            let result = match command.data.name.as_str() {
-                "init" => crate::commands::init::run(&command).await,
-                "verify" => crate::commands::verify::run(&ctx.http, &command).await,
-                "check" => crate::commands::check::run(&command).await,
-                "reset" => crate::commands::reset::run(&ctx.http, &command).await,
-                "info" => crate::commands::info::run(&ctx.http, &command).await,
-                _ => "Oops!".into()
-            };
-            crate::helper::edit_reply(&ctx.http, result, &command).await;
+                let result = match command.data.name.as_str() {
+                    "init" => crate::commands::init::run(&ctx.http, &command).await,
+                    "badgeroles" => crate::commands::badgeroles::run(&ctx.http, &command).await,
+                    "agerole" => crate::commands::agerole::run(&ctx.http, &command).await,
+                    "verify" => crate::commands::verify::run(&ctx.http, &command).await,
+                    "check" => crate::commands::check::run(&command).await,
+                    "reset" => crate::commands::reset::run(&ctx.http, &command).await,
+                    "info" => crate::commands::info::run(&ctx.http, &command).await,
+                    "config" => crate::commands::config::run(&ctx.http, &command).await,
+                    "accounts" => crate::commands::accounts::run(&ctx.http, &command).await,
+                    "link" => crate::commands::link::run(&ctx.http, &command).await,
+                    "unlink" => crate::commands::unlink::run(&ctx.http, &command).await,
+                    _ => "Oops!".into()
+                };
+                crate::helper::edit_reply(&ctx.http, result, &command).await;
+            }
+            Interaction::Component(component) => {
+                // Custom ids are built as "<command>:<action>".
+                let (name, action) = component
+                    .data
+                    .custom_id
+                    .split_once(':')
+                    .unwrap_or((component.data.custom_id.as_str(), ""));
+
+                match name {
+                    "verify" => crate::commands::verify::component(&ctx.http, action, &component).await,
+                    "claim" => crate::conflicts::component(&ctx.http, action, &component).await,
+                    _ => crate::helper::acknowledge(&ctx.http, &component).await,
+                }
+            }
+            _ => {}
         }
     }
 
     async fn ready(&self, ctx: Context, _ready: Ready) {
-        let guild_id = GuildId::new(crate::settings().get_guild().get_id());
+        crate::commands::verify::resume(&ctx.http).await;
+        crate::jobs::spawn(&ctx.http);
+    }
 
-        let _ = guild_id
-            .set_commands(
-                &ctx.http,
-                vec![
-                    crate::commands::init::register(),
-                    crate::commands::verify::register(),
-                    crate::commands::check::register(),
-                    crate::commands::reset::register(),
-                    crate::commands::info::register(),
-                ],
-            )
-            .await;
+    /// Called for every guild after connecting and whenever the bot joins a guild.
+    async fn guild_create(&self, ctx: Context, guild: serenity::all::Guild, _is_new: Option<bool>) {
+        if let Err(e) = guild.id.set_commands(&ctx.http, commands()).await {
+            error!("Cannot register the commands on the guild {}: {}", guild.id, e);
+        }
+    }
+
+    /// Reverts nicknames verified members changed themselves.
+    async fn guild_member_update(
+        &self,
+        ctx: Context,
+        _old: Option<serenity::all::Member>,
+        _new: Option<serenity::all::Member>,
+        event: serenity::all::GuildMemberUpdateEvent,
+    ) {
+        crate::nicknames::revert(&ctx.http, &event).await;
     }
 }
 
-#[derive(Deserialize)]
+fn commands() -> Vec<CreateCommand> {
+    vec![
+        crate::commands::init::register(),
+        crate::commands::verify::register(),
+        crate::commands::accounts::register(),
+        crate::commands::link::register(),
+        crate::commands::unlink::register(),
+        crate::commands::check::register(),
+        crate::commands::reset::register(),
+        crate::commands::info::register(),
+        crate::commands::badgeroles::register(),
+        crate::commands::agerole::register(),
+        crate::commands::config::register(),
+    ]
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
 pub struct Badge {
     pub code: Box<str>,
     pub name: Box<str>,
 }
 
 #[derive(Default, Deserialize)]
+#[serde(default)]
 pub struct Profile {
     #[serde(rename = "uniqueId")]
     pub unique_id: Box<str>,
+    pub name: Box<str>,
     #[serde(rename = "figureString")]
     pub figure_string: Box<str>,
     pub motto: Box<str>,
@@ -194,11 +659,429 @@ pub struct Profile {
     pub member_since: Box<str>,
     #[serde(rename = "selectedBadges")]
     pub selected_badges: Vec<Badge>,
+    #[serde(rename = "profileVisible")]
+    pub profile_visible: Option<bool>,
+}
+
+impl Profile {
+    /// Parses `memberSince`, e.g. `2024-06-18T19:36:25.000+0000`.
+    pub fn member_since(&self) -> Option<bson::DateTime> {
+        if let Ok(date) = bson::DateTime::parse_rfc3339_str(&self.member_since) {
+            return Some(date);
+        }
+
+        // The hotel omits the colon of the offset, which RFC 3339 requires.
+        let s = &*self.member_since;
+        let offset = s.len().checked_sub(5).filter(|i| s.is_char_boundary(*i))?;
+        let (date, zone) = s.split_at(offset);
+        if !zone.starts_with(['+', '-']) || !zone[1..].bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let fixed = format!("{}{}:{}", date, &zone[..3], &zone[3..]);
+        bson::DateTime::parse_rfc3339_str(fixed).ok()
+    }
+}
+
+/// Documents of older versions belong to the default hotel.
+fn default_hotel() -> Box<str> {
+    crate::settings().get_default_hotel().get_id().into()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationState {
+    Pending,
+    Verified,
+    Expired,
+    Revoked,
+    Transferred,
+}
+
+impl VerificationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Verified => "verified",
+            Self::Expired => "expired",
+            Self::Revoked => "revoked",
+            Self::Transferred => "transferred",
+        }
+    }
+
+    /// Returns whether a link in this state may be moved to `to`.
+    /// A transferred link is verified again if the transfer cannot be completed, see `conflicts::transfer`.
+    pub fn can_transition_to(self, to: Self) -> bool {
+        use VerificationState::*;
+
+        matches!(
+            (self, to),
+            (Pending, Verified)
+                | (Pending, Expired)
+                | (Verified, Revoked)
+                | (Verified, Transferred)
+                | (Transferred, Verified)
+                | (Expired | Revoked | Transferred, Pending)
+        )
+    }
+}
+
+impl std::fmt::Display for VerificationState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Transition {
+    pub from: Option<VerificationState>,
+    pub to: VerificationState,
+    pub at: bson::DateTime,
+    pub reason: Box<str>,
+}
+
+/// The configuration of a guild, stored in the collection `guild_configs`.
+/// Guilds without a document use the defaults, i.e. no roles and channels are set.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildConfig {
+    pub guild_id: String,
+    pub verify_role_id: u64,
+    pub age_roles: Vec<AgeRole>,
+    /// Role for verified members whose Habbo is in the hotel right now. Disabled if not set.
+    pub presence_role_id: u64,
+    /// Channel for notifications to moderators. Disabled if not set.
+    pub log_channel_id: u64,
+    /// Whether replies to commands are only visible to the user who executed them.
+    pub ephemeral_replies: bool,
+    pub badge_roles_enabled: bool,
+    pub age_roles_enabled: bool,
+    pub presence_role_enabled: bool,
+    /// What happens if a Habbo is verified which is already linked to another member.
+    pub conflict_policy: ConflictPolicy,
+    /// Channel for claims moderators have to review. The log channel is used if not set.
+    pub mod_channel_id: u64,
+    /// Channel every link event is posted to. Disabled if not set.
+    pub audit_channel_id: u64,
+    /// Nickname of verified members, e.g. `{discord} | {habbo}`. Disabled if empty.
+    pub nickname_template: String,
+    /// How many Habbos a member can link.
+    pub max_accounts: u32,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        Self {
+            guild_id: String::new(),
+            verify_role_id: 0,
+            age_roles: Vec::new(),
+            presence_role_id: 0,
+            log_channel_id: 0,
+            ephemeral_replies: true,
+            badge_roles_enabled: true,
+            age_roles_enabled: true,
+            presence_role_enabled: true,
+            conflict_policy: ConflictPolicy::Transfer,
+            mod_channel_id: 0,
+            audit_channel_id: 0,
+            nickname_template: String::new(),
+            max_accounts: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// The Habbo is taken away from the previous owner.
+    Transfer,
+    /// A moderator has to approve the transfer.
+    Approval,
+    /// The Habbo stays with the previous owner.
+    Deny,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Transfer => "transfer",
+            Self::Approval => "approval",
+            Self::Deny => "deny",
+        }
+    }
+
+    pub fn parse(val: &str) -> Option<Self> {
+        match val.trim().to_lowercase().as_str() {
+            "transfer" => Some(Self::Transfer),
+            "approval" => Some(Self::Approval),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct VerifiedUser {
     pub id: String,
+    /// The id of the guild the link belongs to. Every guild has its own links.
+    #[serde(default)]
+    pub guild: String,
+    /// The display name of the Habbo, refreshed by `jobs::rename`.
     pub habbo: Box<str>,
-    pub verified: bool,
+    /// The immutable id of the Habbo. Missing for links of older versions and for links created with `/link`
+    /// while the profile could not be retrieved, until `jobs::rename` ran. Not stored if missing,
+    /// so these links are not part of the unique indexes.
+    #[serde(default, skip_serializing_if = "str::is_empty")]
+    pub unique_id: Box<str>,
+    /// The id of the hotel the Habbo lives in, see `Settings::get_hotels`.
+    #[serde(default = "default_hotel")]
+    pub hotel: Box<str>,
+    /// When the Habbo has been created, see `Profile::member_since`.
+    #[serde(default)]
+    pub member_since: Option<bson::DateTime>,
+    /// Set by `jobs::revalidate` while the Habbo cannot be found anymore.
+    #[serde(default)]
+    pub flagged_at: Option<bson::DateTime>,
+    #[serde(default)]
+    pub flag_reason: Option<Box<str>>,
+    pub state: VerificationState,
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+    /// Roles and the nickname are granted for the primary Habbo of a member, see `accounts`.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+impl VerifiedUser {
+    pub fn guild_id(&self) -> Option<GuildId> {
+        self.guild.parse::<u64>().ok().filter(|id| *id != 0).map(GuildId::new)
+    }
+
+    /// The transition which moved the link into its current state.
+    pub fn last_transition(&self) -> Option<&Transition> {
+        self.transitions.last()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PendingVerification {
+    pub id: String,
+    #[serde(default)]
+    pub guild: String,
+    pub habbo: Box<str>,
+    #[serde(default)]
+    pub unique_id: Box<str>,
+    #[serde(default = "default_hotel")]
+    pub hotel: Box<str>,
+    pub code: Box<str>,
+    pub token: Box<str>,
+    pub expires_at: bson::DateTime,
+}
+
+impl PendingVerification {
+    pub fn guild_id(&self) -> Option<GuildId> {
+        self.guild.parse::<u64>().ok().filter(|id| *id != 0).map(GuildId::new)
+    }
+}
+
+/// A verification of a Habbo which is already linked to other members, waiting for a moderator.
+#[derive(Serialize, Deserialize)]
+pub struct Claim {
+    #[serde(rename = "_id")]
+    pub id: bson::oid::ObjectId,
+    pub guild: String,
+    /// The Discord user who changed the motto.
+    pub user: String,
+    pub habbo: Box<str>,
+    pub unique_id: Box<str>,
+    pub hotel: Box<str>,
+    /// The Discord users the Habbo is linked to right now.
+    pub owners: Vec<String>,
+    pub created_at: bson::DateTime,
+    /// Set while the decision of a moderator is carried out, so the claim is only reviewed once.
+    #[serde(default)]
+    pub reviewing: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkEventKind {
+    Started,
+    Succeeded,
+    Failed,
+    Reset,
+    Transferred,
+    Revoked,
+    /// A transferred link has been verified again, because the transfer could not be completed.
+    Restored,
+}
+
+impl LinkEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Reset => "reset",
+            Self::Transferred => "transferred",
+            Self::Revoked => "revoked",
+            Self::Restored => "restored",
+        }
+    }
+
+    /// The event of a link moving from the state `from` into the state `to`.
+    pub fn from_transition(from: VerificationState, to: VerificationState) -> Self {
+        match to {
+            VerificationState::Pending => Self::Started,
+            VerificationState::Verified if from == VerificationState::Transferred => Self::Restored,
+            VerificationState::Verified => Self::Succeeded,
+            VerificationState::Expired => Self::Failed,
+            VerificationState::Revoked => Self::Revoked,
+            VerificationState::Transferred => Self::Transferred,
+        }
+    }
+}
+
+/// An entry of the collection `link_events`. Unlike the transitions of a link,
+/// events are kept after the link has been deleted with `/reset`.
+#[derive(Serialize, Deserialize)]
+pub struct LinkEvent {
+    pub guild: String,
+    /// The Discord user the link belongs to.
+    pub user: String,
+    pub kind: LinkEventKind,
+    pub hotel: Box<str>,
+    pub habbo: Box<str>,
+    #[serde(default)]
+    pub unique_id: Box<str>,
+    pub reason: Box<str>,
+    pub at: bson::DateTime,
+}
+
+impl LinkEvent {
+    pub fn new(link: &VerifiedUser, kind: LinkEventKind, reason: &str) -> Self {
+        Self {
+            guild: link.guild.clone(),
+            user: link.id.clone(),
+            kind,
+            hotel: link.hotel.clone(),
+            habbo: link.habbo.clone(),
+            unique_id: link.unique_id.clone(),
+            reason: reason.into(),
+            at: bson::DateTime::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legal_transitions() {
+        use VerificationState::*;
+
+        assert!(Pending.can_transition_to(Verified));
+        assert!(Pending.can_transition_to(Expired));
+        assert!(Verified.can_transition_to(Revoked));
+        assert!(Verified.can_transition_to(Transferred));
+        assert!(Transferred.can_transition_to(Verified));
+        for from in [Expired, Revoked, Transferred] {
+            assert!(from.can_transition_to(Pending));
+        }
+    }
+
+    #[test]
+    fn illegal_transitions() {
+        use VerificationState::*;
+
+        // Links are only verified by a verification or a failed transfer.
+        for from in [Verified, Expired, Revoked] {
+            assert!(!from.can_transition_to(Verified), "{} -> verified", from);
+        }
+        for state in [Pending, Verified, Expired, Revoked, Transferred] {
+            assert!(!state.can_transition_to(state), "{} -> {}", state, state);
+        }
+        assert!(!Pending.can_transition_to(Revoked));
+        assert!(!Pending.can_transition_to(Transferred));
+        assert!(!Verified.can_transition_to(Pending));
+        assert!(!Verified.can_transition_to(Expired));
+        assert!(!Expired.can_transition_to(Revoked));
+        assert!(!Revoked.can_transition_to(Transferred));
+    }
+
+    #[test]
+    fn transition_events() {
+        use VerificationState::*;
+
+        assert_eq!(LinkEventKind::from_transition(Pending, Verified), LinkEventKind::Succeeded);
+        assert_eq!(LinkEventKind::from_transition(Transferred, Verified), LinkEventKind::Restored);
+        assert_eq!(LinkEventKind::from_transition(Verified, Transferred), LinkEventKind::Transferred);
+        assert_eq!(LinkEventKind::from_transition(Transferred, Pending), LinkEventKind::Started);
+    }
+
+    fn profile(member_since: &str) -> Profile {
+        Profile {
+            member_since: member_since.into(),
+            ..Default::default()
+        }
+    }
+
+    fn date(s: &str) -> bson::DateTime {
+        bson::DateTime::parse_rfc3339_str(s).unwrap()
+    }
+
+    #[test]
+    fn member_since_without_colon_in_offset() {
+        let expected = date("2024-06-18T19:36:25Z");
+        assert_eq!(profile("2024-06-18T19:36:25.000+0000").member_since(), Some(expected));
+        assert_eq!(profile("2024-06-18T21:36:25.000+0200").member_since(), Some(expected));
+        assert_eq!(profile("2024-06-18T17:36:25-0200").member_since(), Some(expected));
+    }
+
+    #[test]
+    fn member_since_rfc3339() {
+        let expected = date("2024-06-18T19:36:25Z");
+        assert_eq!(profile("2024-06-18T19:36:25Z").member_since(), Some(expected));
+        assert_eq!(profile("2024-06-18T19:36:25.000+00:00").member_since(), Some(expected));
+    }
+
+    #[test]
+    fn member_since_invalid() {
+        for s in ["", "+0000", "2024-06-18", "2024-06-18T19:36:25.000+00x0", "2024-06-18T19:36:25.000 UTC"] {
+            assert_eq!(profile(s).member_since(), None, "{}", s);
+        }
+    }
+
+    fn age_role(min_days: u64, joined_before: Option<&str>) -> AgeRole {
+        AgeRole::new(1, min_days, joined_before)
+    }
+
+    fn days_ago(days: i64) -> bson::DateTime {
+        bson::DateTime::from_millis(bson::DateTime::now().timestamp_millis() - days * 24 * 60 * 60 * 1000)
+    }
+
+    #[test]
+    fn age_role_min_days() {
+        let role = age_role(365, None);
+        assert!(role.matches(days_ago(366)));
+        assert!(role.matches(days_ago(365)));
+        assert!(!role.matches(days_ago(364)));
+        assert!(age_role(0, None).matches(bson::DateTime::now()));
+    }
+
+    #[test]
+    fn age_role_joined_before() {
+        let role = age_role(0, Some("2024-07-01T00:00:00Z"));
+        assert!(role.matches(date("2024-06-30T23:59:59Z")));
+        assert!(!role.matches(date("2024-07-01T00:00:00Z")));
+        assert!(!age_role(0, Some("launch week")).matches(date("2020-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn age_role_both_conditions() {
+        let role = age_role(30, Some("2024-07-01T00:00:00Z"));
+        assert!(role.matches(date("2024-06-01T00:00:00Z")));
+        assert!(!role.matches(date("2024-08-01T00:00:00Z")));
+        assert!(!age_role(30, Some("2999-01-01T00:00:00Z")).matches(days_ago(1)));
+    }
 }
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use crate::structs::Settings;
+
+/// Smallest stack size tokio can run the bot with.
+const MIN_STACK_SIZE: usize = 64 * 1024;
+
+/// Interaction tokens are valid for 15 minutes, the result of a verification must be sent before.
+const MAX_WINDOW_SECS: u64 = 14 * 60;
+
+/// Mottos in the hotel are limited to this length.
+const MAX_CODE_LEN: usize = 38;
+
+/// A problem with a single value of the settings.
+pub struct Problem {
+    /// Where the value is, e.g. `threads.count`.
+    pub path: String,
+    pub message: String,
+    pub suggestion: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` {}\n  > {}", self.path, self.message, self.suggestion)
+    }
+}
+
+#[derive(Default)]
+struct Problems(Vec<Problem>);
+
+impl Problems {
+    fn push(&mut self, path: impl Into<String>, message: impl Into<String>, suggestion: impl Into<String>) {
+        self.0.push(Problem {
+            path: path.into(),
+            message: message.into(),
+            suggestion: suggestion.into(),
+        });
+    }
+
+    /// Discord ids are optional, `0` disables the feature.
+    fn optional_id(&mut self, path: impl Into<String>, id: u64) {
+        if id != 0 && id < crate::LOWEST_ID {
+            self.push(
+                path,
+                format!("is not a valid Discord id (is {})", id),
+                "Copy the id with the developer mode of Discord or set it to 0",
+            );
+        }
+    }
+}
+
+/// Returns every problem of the settings, so all of them can be fixed at once.
+pub fn validate(settings: &Settings) -> Vec<Problem> {
+    let mut problems = Problems::default();
+
+    if settings.get_token().trim().is_empty() {
+        problems.push(
+            "token",
+            "is empty",
+            "Set the token of the bot from the Discord developer portal or `ORIGINSBOT_TOKEN`",
+        );
+    }
+
+    let mongodb = settings.get_mongodb();
+    if let Err(e) = mongodb::options::ConnectionString::parse(mongodb.get_uri()) {
+        problems.push(
+            "mongodb.uri",
+            format!("is not a valid connection string: {}", e.kind),
+            "Use a connection string like `mongodb://localhost:27017` or `ORIGINSBOT_MONGODB_URI`",
+        );
+    }
+    if mongodb.get_database().trim().is_empty() {
+        problems.push(
+            "mongodb.database",
+            "is empty",
+            "Set the name of the database or `ORIGINSBOT_MONGODB_DATABASE`",
+        );
+    }
+
+    let threads = settings.get_threads();
+    if threads.get_count() == 0 {
+        problems.push(
+            "threads.count",
+            "must be at least 1 (is 0)",
+            "Set it to the number of CPU cores, e.g. 4",
+        );
+    }
+    if threads.get_stack_size() < MIN_STACK_SIZE {
+        problems.push(
+            "threads.stack_size",
+            format!("must be at least {} bytes (is {})", MIN_STACK_SIZE, threads.get_stack_size()),
+            "Set it to 2097152 (2 MiB), which is the default of tokio",
+        );
+    }
+
+    if let Some(guild) = settings.get_guild() {
+        if guild.get_id() < crate::LOWEST_ID {
+            problems.push(
+                "guild.id",
+                format!("is not a valid Discord id (is {})", guild.get_id()),
+                "Copy the id of the server with the developer mode of Discord or remove the section `guild`",
+            );
+        }
+
+        let config = guild.to_config();
+        problems.optional_id("guild.verify_role_id", config.verify_role_id);
+        problems.optional_id("guild.presence_role_id", config.presence_role_id);
+        problems.optional_id("guild.log_channel_id", config.log_channel_id);
+        for (i, age_role) in config.age_roles.iter().enumerate() {
+            if age_role.get_role_id() < crate::LOWEST_ID {
+                problems.push(
+                    format!("guild.age_roles[{}].role_id", i),
+                    format!("is not a valid Discord id (is {})", age_role.get_role_id()),
+                    "Copy the id of the role with the developer mode of Discord",
+                );
+            }
+            if let Some(date) = age_role.get_joined_before() {
+                if bson::DateTime::parse_rfc3339_str(date).is_err() {
+                    problems.push(
+                        format!("guild.age_roles[{}].joined_before", i),
+                        format!("is not a valid date (is `{}`)", date),
+                        "Use an RFC 3339 date like `2024-06-25T00:00:00Z`",
+                    );
+                }
+            }
+        }
+    }
+
+    let verification = settings.get_verification();
+    if verification.get_window() == 0 || verification.get_window() > MAX_WINDOW_SECS {
+        problems.push(
+            "verification.window",
+            format!("must be between 1 and {} seconds (is {})", MAX_WINDOW_SECS, verification.get_window()),
+            "Remove it to use the default of 120 seconds",
+        );
+    }
+    let code_format = verification.get_code_format();
+    if !code_format.contains('#') || code_format.chars().count() > MAX_CODE_LEN {
+        problems.push(
+            "verification.code_format",
+            format!("must contain at least one `#` and at most {} characters (is `{}`)", MAX_CODE_LEN, code_format),
+            "Use a format like `#####` or `ORIGINS-####`",
+        );
+    }
+
+    let hotels = settings.get_hotels();
+    if hotels.is_empty() {
+        problems.push(
+            "hotels",
+            "must contain at least one hotel",
+            "Remove it to use the default hotels",
+        );
+    }
+    let mut ids = HashSet::new();
+    for (i, hotel) in hotels.iter().enumerate() {
+        if !ids.insert(hotel.get_id()) {
+            problems.push(
+                format!("hotels[{}].id", i),
+                format!("`{}` is used more than once", hotel.get_id()),
+                "Give every hotel its own id",
+            );
+        }
+        if reqwest::Url::parse(hotel.get_url()).is_err() {
+            problems.push(
+                format!("hotels[{}].url", i),
+                format!("is not a valid URL (is `{}`)", hotel.get_url()),
+                "Use the address of the hotel like `https://origins.habbo.com`",
+            );
+        }
+    }
+
+    if settings.get_habbo_api().get_timeout() == 0 {
+        problems.push(
+            "habbo_api.timeout",
+            "must be at least 1 second (is 0)",
+            "Remove it to use the default of 10 seconds",
+        );
+    }
+
+    problems.0
+}
+
+/// Formats the problems as a single report for the console or the log.
+pub fn report(problems: &[Problem]) -> String {
+    let mut msg = format!("{} problem(s) found in `{}`:", problems.len(), crate::config::path());
+    for problem in problems {
+        msg.push_str("\n\n");
+        msg.push_str(&problem.to_string());
+    }
+    msg
+}
+
+/// Runs `originsbot --check-config` and returns the exit code of the process.
+pub fn check_config() -> i32 {
+    let settings = match Settings::read(crate::config::path()) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    let problems = validate(&settings);
+    if problems.is_empty() {
+        println!("`{}` is valid", crate::config::path());
+        return 0;
+    }
+
+    eprintln!("{}", report(&problems));
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads settings which are valid, except for the values of `overrides`.
+    fn settings(overrides: serde_json::Value) -> Settings {
+        let mut value = serde_json::json!({
+            "mongodb": {
+                "uri": "mongodb://localhost:27017",
+                "database": "originsbot",
+            },
+            "token": "token",
+            "threads": {
+                "count": 4,
+                "stack_size": 2097152,
+            },
+        });
+        for (key, val) in overrides.as_object().unwrap() {
+            value[key] = val.clone();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn paths(settings: &Settings) -> Vec<String> {
+        validate(settings).into_iter().map(|problem| problem.path).collect()
+    }
+
+    #[test]
+    fn valid_settings() {
+        assert!(paths(&settings(serde_json::json!({}))).is_empty());
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let settings = settings(serde_json::json!({
+            "token": " ",
+            "threads": { "count": 0, "stack_size": 1024 },
+            "habbo_api": { "timeout": 0 },
+        }));
+        assert_eq!(paths(&settings), ["token", "threads.count", "threads.stack_size", "habbo_api.timeout"]);
+    }
+
+    #[test]
+    fn mongodb() {
+        let settings = settings(serde_json::json!({
+            "mongodb": { "uri": "localhost", "database": "" },
+        }));
+        assert_eq!(paths(&settings), ["mongodb.uri", "mongodb.database"]);
+    }
+
+    #[test]
+    fn verification() {
+        for (window, code_format, expected) in [
+            (0, "#####", vec!["verification.window"]),
+            (MAX_WINDOW_SECS + 1, "#####", vec!["verification.window"]),
+            (MAX_WINDOW_SECS, "ORIGINS", vec!["verification.code_format"]),
+            (1, &*"#".repeat(MAX_CODE_LEN + 1), vec!["verification.code_format"]),
+            (120, "ORIGINS-####", vec![]),
+        ] {
+            let settings = settings(serde_json::json!({
+                "verification": { "window": window, "code_format": code_format },
+            }));
+            assert_eq!(paths(&settings), expected, "{} {}", window, code_format);
+        }
+    }
+
+    #[test]
+    fn hotels() {
+        let settings = settings(serde_json::json!({
+            "hotels": [
+                { "id": "com", "name": "Habbo Hotel:Origins (.com)", "url": "https://origins.habbo.com" },
+                { "id": "com", "name": "Habbo Hotel:Origins (.es)", "url": "origins.habbo.es" },
+            ],
+        }));
+        assert_eq!(paths(&settings), ["hotels[1].id", "hotels[1].url"]);
+    }
+
+    #[test]
+    fn no_hotels() {
+        let settings = settings(serde_json::json!({ "hotels": [] }));
+        assert_eq!(paths(&settings), ["hotels"]);
+    }
+
+    #[test]
+    fn legacy_guild() {
+        let settings = settings(serde_json::json!({
+            "guild": {
+                "id": 1,
+                "verify_role_id": 5,
+                "age_roles": [{ "role_id": crate::LOWEST_ID, "joined_before": "launch week" }],
+            },
+        }));
+        assert_eq!(paths(&settings), ["guild.id", "guild.verify_role_id", "guild.age_roles[0].joined_before"]);
+    }
+}